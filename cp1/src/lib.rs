@@ -53,10 +53,27 @@ impl UnsignedLongInt {
         }
     }
 
+    /// removes higher-order zero digits, keeping at least one digit
+    fn truncate_zeroes(&mut self) {
+        let mut new_len = self.underlying_array.len();
+        while new_len > 1 {
+            if self.underlying_array[new_len - 1] > 0 {
+                break;
+            }
+            new_len -= 1;
+        }
+        self.underlying_array.truncate(new_len);
+    }
+
     pub fn num_digits(&self) -> usize {
         self.underlying_array.len()
     }
 
+    /// little-endian view of the underlying 64-bit digits
+    pub fn digits(&self) -> &[u64] {
+        &self.underlying_array
+    }
+
     pub fn add(&self, rhs: &Self) -> Self {
         let (shorter, longer) = if self.underlying_array.len() > rhs.underlying_array.len()
         { (rhs, self) } else { (self, rhs) };
@@ -194,6 +211,8 @@ impl UnsignedLongInt {
             result = UnsignedLongInt::add(&result, &temp);
         }
 
+        // multiplication by zero leaves zero digits behind
+        result.truncate_zeroes();
         result
     }
 
@@ -239,8 +258,29 @@ impl UnsignedLongInt {
         result
     }
 
+    /// returns `self >> rhs`
     pub fn shr(&self, rhs: &Self) -> Self {
-        UnsignedLongInt::div(self, &UnsignedLongInt::from(2).pow(rhs)).0
+        // any shift that does not fit into the allocated bits clears the number
+        if rhs.get_highest_set_bit().is_some_and(|b| b >= u64::BITS as usize)
+            || rhs.underlying_array[0] >= self.get_allocated_bit_length() as u64 {
+            return UnsignedLongInt::from(0);
+        }
+
+        let n = rhs.underlying_array[0] as usize;
+        let digit_shift = n / u64::BITS as usize;
+        let n: u32 = (n % u64::BITS as usize) as u32;
+
+        let mut result = Self::empty_with_capcity(self.underlying_array.len() - digit_shift);
+        for i in digit_shift..self.underlying_array.len() {
+            let carryin = match self.underlying_array.get(i + 1) {
+                Some(next) => u64::checked_shl(*next, u64::BITS - n).unwrap_or(0),
+                None => 0,
+            };
+            result.underlying_array.push((self.underlying_array[i] >> n) | carryin);
+        }
+
+        result.truncate_zeroes();
+        result
     }
 
     pub fn get_allocated_bit_length(&self) -> usize {
@@ -479,6 +519,17 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn mul_zero_test() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str("DEADBEEFDEADBEEFDEADBEEF")?;
+        let zero = UnsignedLongInt::from(0);
+
+        assert_eq!(&a * &zero, zero);
+        assert_eq!(&zero * &a, zero);
+
+        Ok(())
+    }
+
     #[test]
     fn add_test() -> Result<(), Box<dyn Error>> {
         let a = UnsignedLongInt::from_str("DEADBEEFDEADBEEFDEADBEEF")?;
@@ -563,6 +614,17 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn shr2_test() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str("deadbeefdeadbeefdeadbeef")?;
+
+        assert_eq!(a.shr(&UnsignedLongInt::from(0)), a);
+        assert_eq!(a.shr(&UnsignedLongInt::from(68)), UnsignedLongInt::from_str("deadbee")?);
+        assert_eq!(a.shr(&UnsignedLongInt::from(96)), UnsignedLongInt::from(0));
+        assert_eq!(a.shr(&UnsignedLongInt::from_str("10000000000000000")?), UnsignedLongInt::from(0));
+        Ok(())
+    }
+
     #[test]
     fn div_test() -> Result<(), Box<dyn Error>> {
         let a = UnsignedLongInt::from_str("deadbeefdeadbeefdeadbeef")?;
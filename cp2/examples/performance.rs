@@ -3,7 +3,7 @@ use std::ops::Add;
 use vl_big_ints::UnsignedLongInt;
 use vl_big_ints_modulo::*;
 use std::time;
-use vl_big_ints_modulo::context::{ModuloContext, ModuloUint};
+use vl_big_ints_modulo::context::{ModuloContext, ModuloUint, MontgomeryContext, MontgomeryUint};
 
 const NUM_EXPERIMENTS: [usize; 3] = [1000, 10000, 100000];
 const OPERAND_BIT_LENGTH: [usize; 3] = [1024, 2048, 4096];
@@ -24,22 +24,34 @@ fn main() -> Result<(), Box<dyn Error>> {
 
             let mut op1: Vec<ModuloUint> = Vec::with_capacity(experiment_l);
             let mut op2: Vec<ModuloUint> = Vec::with_capacity(experiment_l);
+            let mut mont_op1: Vec<MontgomeryUint> = Vec::with_capacity(experiment_l);
+            let mut mont_op2: Vec<MontgomeryUint> = Vec::with_capacity(experiment_l);
 
-            let mrandom: Vec<u64> = (0..num_digits).map(|_| { rand::random::<u64>() }).collect();
+            let mut mrandom: Vec<u64> = (0..num_digits).map(|_| { rand::random::<u64>() }).collect();
+            // Montgomery reduction needs an odd modulo
+            mrandom[0] |= 1;
             let modulo = UnsignedLongInt::from(mrandom.as_slice());
 
             let mc = ModuloContext::new(&modulo);
+            let montc = MontgomeryContext::new(&modulo).expect("modulo is odd");
 
             for _ in 0..experiment_l {
                 let random_bytes: Vec<u64> = (0..num_digits).map(|_| { rand::random::<u64>() }).collect();
-                op1.push(mc.modulo( &UnsignedLongInt::from(random_bytes.as_slice())));
+                let random_bytes = UnsignedLongInt::from(random_bytes.as_slice());
+                op1.push(mc.modulo(&random_bytes));
+                mont_op1.push(montc.modulo(&random_bytes));
                 let random_bytes: Vec<u64> = (0..num_digits).map(|_| { rand::random::<u64>() }).collect();
-                op2.push(mc.modulo( &UnsignedLongInt::from(random_bytes.as_slice())));
+                let random_bytes = UnsignedLongInt::from(random_bytes.as_slice());
+                op2.push(mc.modulo(&random_bytes));
+                mont_op2.push(montc.modulo(&random_bytes));
             }
             println!("Using modulo: {}", mc.get_modulo());
             for op in ["+", "-", "*"] {
                 measure(op, experiment_l, &op1, &op2, op_len, &mut wrt)?;
             }
+            for op in ["m+", "m-", "m*"] {
+                measure_montgomery(op, experiment_l, &mont_op1, &mont_op2, op_len, &mut wrt)?;
+            }
         }
     }
 
@@ -77,6 +89,36 @@ fn measure(op: &str, exps: usize, op1: &Vec<ModuloUint>, op2: &Vec<ModuloUint>,
     Ok(average_duration)
 }
 
+fn measure_montgomery(op: &str, exps: usize, op1: &[MontgomeryUint], op2: &[MontgomeryUint], oplen: usize, wrt: &mut csv::Writer<std::fs::File>) -> Result<time::Duration, Box<dyn Error>> {
+    println!("Running {exps} experiments on {}-bit bigints with Montgomery reduction; operation: {}", oplen, op);
+
+    let mut durations = Vec::with_capacity(exps);
+    for i in 0..exps {
+        let duration = match op {
+            "m+" => { measure_montgomery_add(&op1[i], &op2[i]) }
+            "m*" => { measure_montgomery_mul(&op1[i], &op2[i]) }
+            "m-" => { measure_montgomery_sub(&op1[i], &op2[i]) }
+            _ => panic!("must be valid op string")
+        };
+
+        durations.push(duration);
+    }
+
+    let total_duration: time::Duration = durations.iter().sum();
+
+    let average_duration = total_duration / durations.len() as u32;
+
+    println!(" - Average operaition duration: {:?}", average_duration);
+    wrt.write_record([
+        op.to_string(),
+        format!("{exps}"),
+        format!("{oplen}"),
+        format!("{}", average_duration.as_nanos()),
+    ])?;
+
+    Ok(average_duration)
+}
+
 fn measure_add(op1: &ModuloUint, op2: &ModuloUint) -> time::Duration {
     let then = time::Instant::now();
     let _ = op1.context().add(op1, op2);
@@ -99,4 +141,28 @@ fn measure_sub(op1: &ModuloUint, op2: &ModuloUint) -> time::Duration {
     let now = time::Instant::now();
 
     now.duration_since(then)
-}
\ No newline at end of file
+}
+
+fn measure_montgomery_add(op1: &MontgomeryUint, op2: &MontgomeryUint) -> time::Duration {
+    let then = time::Instant::now();
+    let _ = op1.context().add(op1, op2);
+    let now = time::Instant::now();
+
+    now.duration_since(then)
+}
+
+fn measure_montgomery_mul(op1: &MontgomeryUint, op2: &MontgomeryUint) -> time::Duration {
+    let then = time::Instant::now();
+    let _ = op1.context().mul(op1, op2);
+    let now = time::Instant::now();
+
+    now.duration_since(then)
+}
+
+fn measure_montgomery_sub(op1: &MontgomeryUint, op2: &MontgomeryUint) -> time::Duration {
+    let then = time::Instant::now();
+    let _ = op1.context().sub(op1, op2);
+    let now = time::Instant::now();
+
+    now.duration_since(then)
+}
@@ -49,5 +49,11 @@ for op in ops:
             optext = 'mul'
         case '-':
             optext = 'sub'
+        case 'm+':
+            optext = 'montadd'
+        case 'm*':
+            optext = 'montmul'
+        case 'm-':
+            optext = 'montsub'
 
     fig.savefig(f'../report/{el}{optext}.png')
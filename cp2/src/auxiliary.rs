@@ -30,4 +30,110 @@ impl BitOps for UnsignedLongInt{
     fn get_highest_set_bit(&self) -> Option<usize> {
         self.get_highest_set_bit()
     }
-}
\ No newline at end of file
+}
+
+/// number of significant bits in `x`
+pub fn bit_length(x: &UnsignedLongInt) -> usize {
+    x.get_highest_set_bit().map_or(0, |b| b + 1)
+}
+
+/// digits of `x` without the leading zero digits, so equal numbers always have equal slices
+pub fn significant_digits(x: &UnsignedLongInt) -> &[u64] {
+    let digits = x.digits();
+    let len = digits.iter().rposition(|&d| d != 0).map_or(0, |i| i + 1);
+    &digits[..len]
+}
+
+/// returns `x mod 2^n` by truncating the digits and masking the top one
+pub fn low_bits(x: &UnsignedLongInt, n: usize) -> UnsignedLongInt {
+    let digit_bits = u64::BITS as usize;
+    let num_digits = n.div_ceil(digit_bits);
+    if num_digits >= x.num_digits() && bit_length(x) <= n {
+        return x.clone();
+    }
+
+    let mut digits = x.digits()[..num_digits].to_vec();
+    if !n.is_multiple_of(digit_bits) {
+        digits[num_digits - 1] &= (1u64 << (n % digit_bits)) - 1;
+    }
+    if digits.is_empty() {
+        return UnsignedLongInt::from(0);
+    }
+
+    UnsignedLongInt::from(digits.as_slice())
+}
+
+/// returns `a * b mod m` for word-sized numbers
+pub fn mul_mod_word(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+/// returns `a^e mod m` for word-sized numbers
+pub fn pow_mod_word(a: u64, mut e: u64, m: u64) -> u64 {
+    let mut a = a % m;
+    let mut c = 1 % m;
+    while e != 0 {
+        if e & 1 == 1 {
+            c = mul_mod_word(c, a, m);
+        }
+        a = mul_mod_word(a, a, m);
+        e >>= 1;
+    }
+
+    c
+}
+
+/// returns `a^-1 mod m` for word-sized numbers if it exists
+pub fn inv_mod_word(a: u64, m: u64) -> Option<u64> {
+    let (mut old_r, mut r) = (a as i128 % m as i128, m as i128);
+    let (mut old_x, mut x) = (1i128, 0i128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+    }
+
+    if old_r != 1 {
+        return None;
+    }
+
+    Some(old_x.rem_euclid(m as i128) as u64)
+}
+
+/// deterministic Miller-Rabin test for word-sized numbers
+pub fn is_prime_word(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+    if n < 2 {
+        return false;
+    }
+    for p in BASES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    'witness: for a in BASES {
+        let mut x = pow_mod_word(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod_word(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+
+        return false;
+    }
+
+    true
+}
+
+/// returns `x mod m` for a word-sized `m`
+pub fn rem_word(x: &UnsignedLongInt, m: u64) -> u64 {
+    x.digits().iter().rev().fold(0u64, |r, &d| ((((r as u128) << 64) | d as u128) % m as u128) as u64)
+}
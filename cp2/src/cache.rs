@@ -0,0 +1,188 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::significant_digits;
+use crate::context::SharedModuloContext;
+
+/// capacity of the cache behind the free functions of the crate root
+pub const DEFAULT_CAPACITY: usize = 64;
+
+static GLOBAL: OnceLock<ContextCache> = OnceLock::new();
+
+/// cache used by [`crate::add_mod`], [`crate::mul_mod`], [`crate::power_mod_barret`] and friends
+pub fn global() -> &'static ContextCache {
+    GLOBAL.get_or_init(|| ContextCache::new(DEFAULT_CAPACITY))
+}
+
+/// hit and miss counters of a [`ContextCache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+impl CacheStats {
+    /// fraction of lookups served from the cache, 0 if there were none
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+struct CacheState {
+    contexts: HashMap<Vec<u64>, SharedModuloContext>,
+    /// keys from the least to the most recently used
+    order: VecDeque<Vec<u64>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheState {
+    /// marks `key` as the most recently used one
+    fn touch(&mut self, key: &[u64]) {
+        if let Some(i) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(i).expect("position is in range");
+            self.order.push_back(key);
+        }
+    }
+}
+
+/// bounded thread-safe cache of contexts keyed by modulo, evicts the least recently used one when full
+pub struct ContextCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+impl ContextCache {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "cache capacity must be positive");
+        Self {
+            capacity,
+            state: Mutex::new(CacheState {
+                contexts: HashMap::with_capacity(capacity),
+                order: VecDeque::with_capacity(capacity),
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// returns the cached context for `modulo`, building (and caching) it on a miss
+    ///
+    /// The context is built without holding the lock, a context another thread cached for the
+    /// same modulo in the meantime is returned instead.
+    pub fn get(&self, modulo: &UnsignedLongInt) -> SharedModuloContext {
+        let key = significant_digits(modulo).to_vec();
+        {
+            let mut state = self.lock();
+            if let Some(context) = state.contexts.get(&key).cloned() {
+                state.hits += 1;
+                state.touch(&key);
+                return context;
+            }
+            state.misses += 1;
+        }
+
+        let context = SharedModuloContext::new(modulo.clone());
+
+        let mut state = self.lock();
+        if !state.contexts.contains_key(&key) && state.contexts.len() == self.capacity {
+            if let Some(oldest) = state.order.pop_front() {
+                state.contexts.remove(&oldest);
+            }
+        }
+        match state.contexts.entry(key.clone()) {
+            Entry::Occupied(cached) => {
+                let context = cached.get().clone();
+                state.touch(&key);
+                context
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(context.clone());
+                state.order.push_back(key);
+                context
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// drops all cached contexts and resets the counters
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.contexts.clear();
+        state.order.clear();
+        state.hits = 0;
+        state.misses = 0;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.lock();
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            len: state.contexts.len(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_and_eviction() {
+        let cache = ContextCache::new(2);
+        let (m1, m2, m3) = (UnsignedLongInt::from(1000003), UnsignedLongInt::from(1000033), UnsignedLongInt::from(1000037));
+
+        cache.get(&m1);
+        cache.get(&m2);
+        assert_eq!(cache.get(&m1).get_modulo(), &m1);
+        // m2 is the least recently used one now
+        cache.get(&m3);
+        cache.get(&m1);
+        cache.get(&m2);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.len), (2, 4, 2));
+        assert_eq!(stats.hit_rate(), 2.0 / 6.0);
+
+        cache.clear();
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 0, len: 0, capacity: 2 });
+        assert_eq!(cache.stats().hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn unnormalized_keys() {
+        let cache = ContextCache::new(4);
+        cache.get(&UnsignedLongInt::from(1000003));
+        cache.get(&UnsignedLongInt::from([1000003, 0].as_slice()));
+
+        assert_eq!(cache.stats().hits, 1);
+    }
+
+    #[test]
+    fn concurrent_misses() {
+        let cache = ContextCache::new(4);
+        let m = UnsignedLongInt::from(1000003);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| assert_eq!(cache.get(&m).get_modulo(), &m));
+            }
+        });
+
+        // racing misses build their own contexts, but only one of them is kept
+        let stats = cache.stats();
+        assert_eq!((stats.hits + stats.misses, stats.len), (8, 1));
+    }
+}
@@ -0,0 +1,187 @@
+use std::marker::PhantomData;
+use std::ops::*;
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::BitOps;
+use crate::context::ModuloContext;
+
+/// modulus known at compile time, usually implemented with [`crate::static_modulus!`]
+///
+/// Every modulus gets its own marker type, so numbers modulo different moduli have different
+/// types and mixing them is a compile error rather than a runtime check.
+pub trait StaticModulus: 'static {
+    /// lazily built context shared by all numbers of this modulus
+    fn context() -> &'static ModuloContext<'static>;
+}
+
+/// declares a marker type implementing [`context::StaticModulus`](crate::context::StaticModulus)
+/// for a modulus given as a hex string
+#[macro_export]
+macro_rules! static_modulus {
+    ($vis:vis $name:ident, $hex:literal) => {
+        $vis struct $name;
+
+        impl $crate::context::StaticModulus for $name {
+            fn context() -> &'static $crate::context::ModuloContext<'static> {
+                static CONTEXT: ::std::sync::OnceLock<$crate::context::ModuloContext<'static>> = ::std::sync::OnceLock::new();
+                CONTEXT.get_or_init(|| {
+                    let modulo = <$crate::UnsignedLongInt as ::std::str::FromStr>::from_str($hex)
+                        .expect("modulus must be a valid hex string");
+                    $crate::context::ModuloContext::new_owned(modulo)
+                })
+            }
+        }
+    };
+}
+
+/// number modulo `M`, numbers with different moduli can not be mixed
+///
+/// ```
+/// use vl_big_ints_modulo::{static_modulus, UnsignedLongInt};
+/// use vl_big_ints_modulo::context::StaticModuloUint;
+///
+/// static_modulus!(A, "f4243");
+///
+/// let a = StaticModuloUint::<A>::new(&UnsignedLongInt::from(1000000));
+/// let b = StaticModuloUint::<A>::new(&UnsignedLongInt::from(4));
+/// assert_eq!(UnsignedLongInt::from(a + b), UnsignedLongInt::from(1));
+/// ```
+///
+/// Adding numbers with different moduli does not compile:
+///
+/// ```compile_fail
+/// use vl_big_ints_modulo::{static_modulus, UnsignedLongInt};
+/// use vl_big_ints_modulo::context::StaticModuloUint;
+///
+/// static_modulus!(A, "f4243");
+/// static_modulus!(B, "f4249");
+///
+/// let a = StaticModuloUint::<A>::new(&UnsignedLongInt::from(1000000));
+/// let b = StaticModuloUint::<B>::new(&UnsignedLongInt::from(4));
+/// let _ = a + b;
+/// ```
+pub struct StaticModuloUint<M: StaticModulus> {
+    val: UnsignedLongInt,
+    modulus: PhantomData<M>,
+}
+
+impl<M: StaticModulus> Clone for StaticModuloUint<M> {
+    fn clone(&self) -> Self {
+        Self::wrap(self.val.clone())
+    }
+}
+
+impl<M: StaticModulus> StaticModuloUint<M> {
+    fn wrap(val: UnsignedLongInt) -> Self {
+        Self { val, modulus: PhantomData }
+    }
+
+    /// returns `a mod M`
+    pub fn new(a: &UnsignedLongInt) -> Self {
+        Self::wrap(M::context().reduce_value(a))
+    }
+
+    pub fn modulo() -> &'static UnsignedLongInt {
+        M::context().get_modulo()
+    }
+
+    /// returns `self^-1` if it exists
+    pub fn inv(&self) -> Option<Self> {
+        M::context().inv_value(&self.val).map(Self::wrap)
+    }
+
+    /// returns `self / rhs`, or `None` if `rhs` is not invertible
+    pub fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        rhs.inv().map(|rhs_inv| self * rhs_inv)
+    }
+
+    /// returns `self^e`
+    pub fn pow<T: BitOps>(&self, e: &T) -> Self {
+        Self::wrap(M::context().pow_value(&self.val, e))
+    }
+
+    /// returns `self^e`
+    pub fn pow_u64(&self, e: u64) -> Self {
+        self.pow(&UnsignedLongInt::from(e))
+    }
+}
+
+impl<M: StaticModulus> From<StaticModuloUint<M>> for UnsignedLongInt {
+    fn from(value: StaticModuloUint<M>) -> Self {
+        value.val
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<M: StaticModulus> Add<&StaticModuloUint<M>> for &StaticModuloUint<M>
+{
+    type Output = StaticModuloUint<M>;
+    fn add(self, rhs: &StaticModuloUint<M>) -> Self::Output {
+        StaticModuloUint::wrap(M::context().add_values(&self.val, &rhs.val))
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<M: StaticModulus> Sub<&StaticModuloUint<M>> for &StaticModuloUint<M>
+{
+    type Output = StaticModuloUint<M>;
+    fn sub(self, rhs: &StaticModuloUint<M>) -> Self::Output {
+        StaticModuloUint::wrap(M::context().sub_values(&self.val, &rhs.val))
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<M: StaticModulus> Mul<&StaticModuloUint<M>> for &StaticModuloUint<M>
+{
+    type Output = StaticModuloUint<M>;
+    fn mul(self, rhs: &StaticModuloUint<M>) -> Self::Output {
+        StaticModuloUint::wrap(M::context().mul_values(&self.val, &rhs.val))
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<M: StaticModulus> Div<&StaticModuloUint<M>> for &StaticModuloUint<M>
+{
+    type Output = StaticModuloUint<M>;
+    /// panics if `rhs` is not invertible, see [`StaticModuloUint::checked_div`]
+    fn div(self, rhs: &StaticModuloUint<M>) -> Self::Output {
+        self.checked_div(rhs).expect("divisor must be invertible")
+    }
+}
+
+impl<M: StaticModulus> Neg for StaticModuloUint<M>
+{
+    type Output = StaticModuloUint<M>;
+    fn neg(self) -> Self::Output {
+        StaticModuloUint::wrap(M::context().neg_value(&self.val))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    crate::static_modulus!(P127, "7fffffffffffffffffffffffffffffff");
+    crate::static_modulus!(Small, "f4243");
+
+    #[test]
+    fn static_arithmetic() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784e")?;
+        let b = UnsignedLongInt::from_str("3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f135")?;
+
+        let m = UnsignedLongInt::from_str("7fffffffffffffffffffffffffffffff")?;
+        let mc = ModuloContext::new(&m);
+
+        let (sa, sb) = (StaticModuloUint::<P127>::new(&a), StaticModuloUint::<P127>::new(&b));
+        let (amod, bmod) = (mc.modulo(&a), mc.modulo(&b));
+
+        let result: UnsignedLongInt = (&sa * &sb + &sa).into();
+        assert_eq!(result, mc.add(&mc.mul(&amod, &bmod), &amod).into());
+        let result: UnsignedLongInt = (&sa / &sb * &sb).into();
+        assert_eq!(result, sa.into());
+        assert_eq!(StaticModuloUint::<Small>::modulo(), &UnsignedLongInt::from(1000003));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,319 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::mem::size_of;
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::BitOps;
+use crate::context::{ModuloContext, ModuloUint};
+use crate::exponentiation::{get_window, MAX_WINDOW_WIDTH};
+use crate::reduction::{AutoReducer, Reducer};
+
+/// default window width of [`FixedBasePow::new`]
+const DEFAULT_WINDOW: usize = 4;
+
+/// magic bytes of the serialized table, the last byte is the format version
+const MAGIC: &[u8; 4] = b"FBP1";
+
+/// smallest serialized number: a `u32` digit count and one `u64` digit
+const MIN_NUMBER_BYTES: usize = 4 + 8;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct FixedBaseFormatError;
+
+impl Display for FixedBaseFormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Serialized fixed-base table is malformed or was built for another modulo")
+    }
+}
+
+impl Error for FixedBaseFormatError {}
+
+/// exponentiation of a fixed base with a precomputed windowed table:
+/// `base^(d * 2^(k * i))` for every `k`-bit window `i` and digit `d`, so `pow` needs
+/// no squarings at all, only one multiplication per non-zero window of the exponent
+pub struct FixedBasePow<'c, R = AutoReducer> {
+    context: &'c ModuloContext<'c, R>,
+    base: UnsignedLongInt,
+    k: usize,
+    max_bits: usize,
+    /// `table[i][d - 1] = base^(d * 2^(k * i))`
+    table: Vec<Vec<UnsignedLongInt>>,
+}
+
+impl<'c, R: Reducer> FixedBasePow<'c, R> {
+    /// precomputes the table for exponents shorter than `max_bits` bits with 4-bit windows
+    pub fn new(context: &'c ModuloContext<'c, R>, base: &ModuloUint<R>, max_bits: usize) -> Self {
+        Self::with_window(context, base, max_bits, DEFAULT_WINDOW)
+    }
+
+    /// precomputes the table with `k`-bit windows, `ceil(max_bits / k) * (2^k - 1)` numbers in total
+    ///
+    /// panics unless `1 <= k <= MAX_WINDOW_WIDTH`
+    pub fn with_window(context: &'c ModuloContext<'c, R>, base: &ModuloUint<R>, max_bits: usize, k: usize) -> Self {
+        assert!((1..=MAX_WINDOW_WIDTH).contains(&k), "window width must be between 1 and {MAX_WINDOW_WIDTH}");
+        context.debug_check_context(&[base]);
+
+        let windows = max_bits.div_ceil(k).max(1);
+        let mut table = Vec::with_capacity(windows);
+        // base^(2^(k * i))
+        let mut g = base.val.clone();
+        for _ in 0..windows {
+            let mut row = Vec::with_capacity((1 << k) - 1);
+            row.push(g.clone());
+            for d in 1..((1 << k) - 1) {
+                row.push(context.mul_values(&row[d - 1], &g));
+            }
+            g = context.mul_values(&row[row.len() - 1], &g);
+            table.push(row);
+        }
+
+        Self { context, base: base.val.clone(), k, max_bits: windows * k, table }
+    }
+
+    pub fn context(&self) -> &'c ModuloContext<'c, R> {
+        self.context
+    }
+
+    /// exponents below `2^max_bits()` are served from the table
+    pub fn max_bits(&self) -> usize {
+        self.max_bits
+    }
+
+    /// returns `base^e`, exponents longer than [`FixedBasePow::max_bits`] fall back to [`ModuloContext::pow`]
+    pub fn pow<T: BitOps>(&self, e: &T) -> ModuloUint<'c, R> {
+        let highest_bit = match e.get_highest_set_bit() {
+            Some(b) => b,
+            None => return self.context.one(),
+        };
+        if highest_bit >= self.max_bits {
+            return ModuloUint {
+                val: self.context.pow_value(&self.base, e),
+                context: self.context,
+            };
+        }
+
+        let mut c: Option<UnsignedLongInt> = None;
+        for (i, row) in self.table.iter().enumerate().take(highest_bit / self.k + 1) {
+            let d = get_window(e, i * self.k, self.k, highest_bit);
+            if d != 0 {
+                c = Some(match c {
+                    Some(c) => self.context.mul_values(&c, &row[d - 1]),
+                    None => row[d - 1].clone(),
+                });
+            }
+        }
+
+        match c {
+            Some(val) => ModuloUint { val: self.context.canonical(val), context: self.context },
+            None => self.context.one(),
+        }
+    }
+
+    /// approximate heap and inline memory taken by the table in bytes
+    pub fn memory_footprint(&self) -> usize {
+        let numbers: usize = self.table.iter().map(|row| row.len()).sum();
+        let digits: usize = self.table.iter().flatten().map(|x| x.num_digits()).sum();
+
+        size_of::<Self>()
+            + self.table.len() * size_of::<Vec<UnsignedLongInt>>()
+            + numbers * size_of::<UnsignedLongInt>()
+            + (digits + self.base.num_digits()) * size_of::<u64>()
+    }
+
+    /// serializes the table: magic `FBP1`, window width and number of windows as little-endian `u32`s,
+    /// then the modulo, the base and every table entry as a `u32` digit count followed by
+    /// little-endian `u64` digits
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.memory_footprint());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(self.k as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.table.len() as u32).to_le_bytes());
+
+        write_number(&mut bytes, self.context.get_modulo());
+        write_number(&mut bytes, &self.base);
+        for x in self.table.iter().flatten() {
+            write_number(&mut bytes, x);
+        }
+
+        bytes
+    }
+
+    /// restores a table serialized with [`FixedBasePow::to_bytes`], which must have been built for
+    /// the same modulo as `context`
+    pub fn from_bytes(context: &'c ModuloContext<'c, R>, bytes: &[u8]) -> Result<Self, FixedBaseFormatError> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(FixedBaseFormatError);
+        }
+
+        let k = reader.read_u32()? as usize;
+        let windows = reader.read_u32()? as usize;
+        if !(1..=MAX_WINDOW_WIDTH).contains(&k) || windows == 0 {
+            return Err(FixedBaseFormatError);
+        }
+        if &reader.read_number()? != context.get_modulo() {
+            return Err(FixedBaseFormatError);
+        }
+
+        let read_residue = |reader: &mut Reader| {
+            let x = reader.read_number()?;
+            if &x < context.get_modulo() { Ok(x) } else { Err(FixedBaseFormatError) }
+        };
+
+        let base = read_residue(&mut reader)?;
+        // the header must not promise more entries than the remaining bytes can hold
+        let entries = windows.checked_mul((1 << k) - 1).ok_or(FixedBaseFormatError)?;
+        if entries.checked_mul(MIN_NUMBER_BYTES).is_none_or(|n| n > reader.bytes.len()) {
+            return Err(FixedBaseFormatError);
+        }
+
+        let mut table = Vec::with_capacity(windows);
+        for _ in 0..windows {
+            let row = (0..((1 << k) - 1)).map(|_| read_residue(&mut reader)).collect::<Result<Vec<_>, _>>()?;
+            table.push(row);
+        }
+        if !reader.bytes.is_empty() {
+            return Err(FixedBaseFormatError);
+        }
+
+        Ok(Self { context, base, k, max_bits: windows * k, table })
+    }
+}
+
+fn write_number(bytes: &mut Vec<u8>, x: &UnsignedLongInt) {
+    bytes.extend_from_slice(&(x.num_digits() as u32).to_le_bytes());
+    for digit in x.digits() {
+        bytes.extend_from_slice(&digit.to_le_bytes());
+    }
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+}
+
+impl<'b> Reader<'b> {
+    fn take(&mut self, n: usize) -> Result<&'b [u8], FixedBaseFormatError> {
+        if self.bytes.len() < n {
+            return Err(FixedBaseFormatError);
+        }
+
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, FixedBaseFormatError> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn read_number(&mut self) -> Result<UnsignedLongInt, FixedBaseFormatError> {
+        let num_digits = self.read_u32()? as usize;
+        let digits: Vec<u64> = self
+            .take(num_digits.checked_mul(8).ok_or(FixedBaseFormatError)?)?
+            .chunks(8)
+            .map(|chunk| {
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(chunk);
+                u64::from_le_bytes(buf)
+            })
+            .collect();
+
+        if digits.is_empty() {
+            return Err(FixedBaseFormatError);
+        }
+
+        Ok(UnsignedLongInt::from(digits.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const M: &str = "463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b";
+
+    #[test]
+    fn matches_pow() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str(M)?;
+        let mc = ModuloContext::new(&m);
+        let g = mc.from_u64(5);
+
+        for k in [1, 3, 4, 6] {
+            let fixed = FixedBasePow::with_window(&mc, &g, 256, k);
+            for e in ["0", "1", "10", "ffff", "ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784e"] {
+                let e = UnsignedLongInt::from_str(e)?;
+                assert_eq!(fixed.pow(&e), g.pow(&e));
+            }
+        }
+
+        // longer exponents fall back to ordinary exponentiation
+        let fixed = FixedBasePow::new(&mc, &g, 64);
+        let e = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca677")?;
+        assert_eq!(fixed.pow(&e), g.pow(&e));
+
+        Ok(())
+    }
+
+    #[test]
+    fn memory_footprint() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str(M)?;
+        let mc = ModuloContext::new(&m);
+        let g = mc.from_u64(5);
+
+        let narrow = FixedBasePow::with_window(&mc, &g, 256, 2);
+        let wide = FixedBasePow::with_window(&mc, &g, 256, 6);
+        // 128 * 3 vs 43 * 63 numbers of 4 digits each
+        assert!(narrow.memory_footprint() > 128 * 3 * 4 * 8);
+        assert!(wide.memory_footprint() > narrow.memory_footprint());
+
+        Ok(())
+    }
+
+    #[test]
+    fn serialization() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str(M)?;
+        let mc = ModuloContext::new(&m);
+        let g = mc.from_u64(5);
+        let e = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784e")?;
+
+        let bytes = FixedBasePow::with_window(&mc, &g, 256, 3).to_bytes();
+        let other_mc = ModuloContext::new(&m);
+        let restored = FixedBasePow::from_bytes(&other_mc, &bytes)?;
+        assert_eq!(restored.max_bits(), 258);
+        assert_eq!(restored.pow(&e).value(), g.pow(&e).value());
+
+        assert_eq!(FixedBasePow::from_bytes(&mc, &bytes[..bytes.len() - 1]).err(), Some(FixedBaseFormatError));
+        assert_eq!(FixedBasePow::from_bytes(&mc, b"FBP0").err(), Some(FixedBaseFormatError));
+        let other_m = UnsignedLongInt::from(1000003);
+        let wrong_mc = ModuloContext::new(&other_m);
+        assert_eq!(FixedBasePow::from_bytes(&wrong_mc, &bytes).err(), Some(FixedBaseFormatError));
+
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_header() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str(M)?;
+        let mc = ModuloContext::new(&m);
+        let bytes = FixedBasePow::with_window(&mc, &mc.from_u64(5), 256, 3).to_bytes();
+
+        let with_header = |k: u32, windows: u32| {
+            let mut forged = bytes.clone();
+            forged[4..8].copy_from_slice(&k.to_le_bytes());
+            forged[8..12].copy_from_slice(&windows.to_le_bytes());
+            forged
+        };
+
+        // an oversized table must be rejected before anything is allocated for it
+        assert_eq!(FixedBasePow::from_bytes(&mc, &with_header(4, u32::MAX)).err(), Some(FixedBaseFormatError));
+        assert_eq!(FixedBasePow::from_bytes(&mc, &with_header(3, 87)).err(), Some(FixedBaseFormatError));
+        for k in [0, MAX_WINDOW_WIDTH as u32 + 1, 63, u32::MAX] {
+            assert_eq!(FixedBasePow::from_bytes(&mc, &with_header(k, 1)).err(), Some(FixedBaseFormatError));
+        }
+        assert_eq!(FixedBasePow::from_bytes(&mc, &bytes[..10]).err(), Some(FixedBaseFormatError));
+
+        Ok(())
+    }
+}
@@ -1,27 +1,182 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::iter::{Product, Sum};
+use std::sync::atomic::{AtomicU64, Ordering};
 use vl_big_ints::UnsignedLongInt;
-use crate::{barret_reduction, BarretParameters};
-use crate::auxiliary::BitOps;
+use crate::{mod_inverse, SignedLongInt};
+use crate::auxiliary::{mul_mod_word, rem_word, significant_digits, BitOps};
+use crate::exponentiation::{MAX_WINDOW_WIDTH, multi_pow_pippenger, multi_pow_straus, pow_k_ary, pow_left_to_right, pow_right_to_left, pow_sliding_window, window_width};
+use crate::reduction::{AutoReducer, Reducer};
 
 pub mod ops;
+pub mod branded;
+pub mod fixed_base;
+pub mod montgomery;
+pub mod shared;
+pub mod small;
+
+pub use branded::{StaticModulus, StaticModuloUint};
+pub use fixed_base::{FixedBaseFormatError, FixedBasePow};
+pub use montgomery::{MontgomeryContext, MontgomeryUint};
+pub use shared::{SharedModuloContext, SharedModuloUint};
+pub use small::{SmallModuloContext, SmallModuloUint};
+
+/// number of terms from which [`ModuloContext::multi_pow`] switches from Straus to Pippenger
+const PIPPENGER_THRESHOLD: usize = 32;
+
+/// source of unique context ids, see [`ModuloContext::id`]
+static NEXT_CONTEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ContextMismatchError;
+
+impl Display for ContextMismatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Numbers were taken by different modulo")
+    }
+}
+
+impl Error for ContextMismatchError {}
 
-pub struct ModuloUint<'c> {
+#[derive(Debug, PartialEq, Eq)]
+pub struct FromStrRadixError;
+
+impl Display for FromStrRadixError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Conversion from string with the given radix resulted in an error")
+    }
+}
+
+impl Error for FromStrRadixError {}
+
+/// returned by [`ModuloContext::batch_inverse`], holds the index of the first non-invertible number
+#[derive(Debug, PartialEq, Eq)]
+pub struct NotInvertibleError {
+    pub index: usize,
+}
+
+impl Display for NotInvertibleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Number at index {} is not invertible", self.index)
+    }
+}
+
+impl Error for NotInvertibleError {}
+
+pub struct ModuloUint<'c, R = AutoReducer> {
     val: UnsignedLongInt,
-    context: &'c ModuloContext<'c>,
+    context: &'c ModuloContext<'c, R>,
+}
+
+impl<R> Clone for ModuloUint<'_, R> {
+    fn clone(&self) -> Self {
+        Self { val: self.val.clone(), context: self.context }
+    }
 }
 
-impl ModuloUint<'_>{
-    pub fn context(&self) -> &ModuloContext{
+impl<R> ModuloUint<'_, R> {
+    /// the residue in `[0, modulo)`
+    pub fn value(&self) -> &UnsignedLongInt {
+        &self.val
+    }
+
+    pub fn is_zero(&self) -> bool {
+        significant_digits(&self.val).is_empty()
+    }
+
+    pub fn is_one(&self) -> bool {
+        significant_digits(&self.val) == [1]
+    }
+}
+
+impl<R: Reducer> ModuloUint<'_, R>{
+    pub fn context(&self) -> &ModuloContext<'_, R>{
         self.context
     }
 }
 
-impl Into<UnsignedLongInt> for ModuloUint<'_> {
-    fn into(self) -> UnsignedLongInt {
-        self.val
+impl<'c, R: Reducer> ModuloUint<'c, R> {
+    /// returns `self / rhs`, or `None` if `rhs` is not invertible
+    pub fn checked_div(&self, rhs: &ModuloUint<R>) -> Option<ModuloUint<'c, R>> {
+        self.context.div(self, rhs)
+    }
+
+    /// returns `self^e`
+    pub fn pow<T: BitOps>(&self, e: &T) -> ModuloUint<'c, R> {
+        self.context.pow(self, e)
+    }
+
+    /// returns `self^e`
+    pub fn pow_u64(&self, e: u64) -> ModuloUint<'c, R> {
+        self.context.pow(self, &UnsignedLongInt::from(e))
+    }
+}
+
+impl<R> From<ModuloUint<'_, R>> for UnsignedLongInt {
+    fn from(value: ModuloUint<'_, R>) -> Self {
+        value.val
+    }
+}
+
+impl<R> Debug for ModuloUint<'_, R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (mod {})", self.val, self.context.modulo)
+    }
+}
+
+impl<R> Display for ModuloUint<'_, R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.val)
+    }
+}
+
+impl<R: Reducer> PartialEq for ModuloUint<'_, R> {
+    /// numbers are equal if they have the same residue and were taken by compatible contexts
+    fn eq(&self, other: &Self) -> bool {
+        significant_digits(&self.val) == significant_digits(&other.val) && self.context.is_compatible(other.context)
+    }
+}
+
+impl<R: Reducer> Eq for ModuloUint<'_, R> {}
+
+impl<R> Hash for ModuloUint<'_, R> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        significant_digits(&self.val).hash(state);
+        significant_digits(&self.context.modulo).hash(state);
+    }
+}
+
+impl<'c, R: Reducer> Sum for ModuloUint<'c, R> {
+    /// panics on an empty iterator, as there is no context to take zero from
+    fn sum<I: Iterator<Item = Self>>(mut iter: I) -> Self {
+        let first = iter.next().expect("sum of an empty iterator has no context");
+        iter.fold(first, |acc, x| acc.context.add(&acc, &x))
     }
 }
 
-impl BitOps for ModuloUint<'_> {
+impl<'a, 'c, R: Reducer> Sum<&'a ModuloUint<'c, R>> for ModuloUint<'c, R> {
+    fn sum<I: Iterator<Item = &'a ModuloUint<'c, R>>>(iter: I) -> Self {
+        iter.cloned().sum()
+    }
+}
+
+impl<'c, R: Reducer> Product for ModuloUint<'c, R> {
+    /// panics on an empty iterator, as there is no context to take one from
+    fn product<I: Iterator<Item = Self>>(mut iter: I) -> Self {
+        let first = iter.next().expect("product of an empty iterator has no context");
+        iter.fold(first, |acc, x| acc.context.mul(&acc, &x))
+    }
+}
+
+impl<'a, 'c, R: Reducer> Product<&'a ModuloUint<'c, R>> for ModuloUint<'c, R> {
+    fn product<I: Iterator<Item = &'a ModuloUint<'c, R>>>(iter: I) -> Self {
+        iter.cloned().product()
+    }
+}
+
+impl<R> BitOps for ModuloUint<'_, R> {
     fn get_bit(&self, i: usize) -> bool {
         self.val.get_bit(i)
     }
@@ -30,86 +185,424 @@ impl BitOps for ModuloUint<'_> {
     }
 }
 
-pub struct ModuloContext<'m> {
-    modulo: &'m UnsignedLongInt,
-    bp: BarretParameters<'m>,
+pub struct ModuloContext<'m, R = AutoReducer> {
+    id: u64,
+    modulo: Cow<'m, UnsignedLongInt>,
+    reducer: R,
+    /// `BASE^n mod modulo` for an `n`-digit modulo, folds inputs of any length
+    fold: UnsignedLongInt,
 }
 
 
 impl<'m> ModuloContext<'m> {
-    // TODO how do you make sure the numbers were taken by the same modulo???
-    /// constucts new context for modular calculations
+    /// constucts new context for modular calculations, the reducer is picked from the shape of `modulo`
     pub fn new(modulo: &'m UnsignedLongInt) -> Self {
         Self {
-            modulo,
-            bp: BarretParameters::new(modulo, modulo.num_digits()),
+            id: NEXT_CONTEXT_ID.fetch_add(1, Ordering::Relaxed),
+            modulo: Cow::Borrowed(modulo),
+            reducer: AutoReducer::new(modulo),
+            fold: fold_factor(modulo),
+        }
+    }
+}
+
+impl ModuloContext<'static> {
+    /// same as [`ModuloContext::new`], but the context owns `modulo`
+    pub fn new_owned(modulo: UnsignedLongInt) -> Self {
+        Self {
+            id: NEXT_CONTEXT_ID.fetch_add(1, Ordering::Relaxed),
+            reducer: AutoReducer::new(&modulo),
+            fold: fold_factor(&modulo),
+            modulo: Cow::Owned(modulo),
+        }
+    }
+}
+
+impl<'m, R: Reducer> ModuloContext<'m, R> {
+    /// constucts new context for modular calculations with an explicitly chosen reducer
+    pub fn with_reducer(modulo: &'m UnsignedLongInt, reducer: R) -> Self {
+        assert!(reducer.modulus() == modulo, "reducer must be built for the same modulo");
+        Self {
+            id: NEXT_CONTEXT_ID.fetch_add(1, Ordering::Relaxed),
+            modulo: Cow::Borrowed(modulo),
+            reducer,
+            fold: fold_factor(modulo),
         }
     }
 
     pub fn get_modulo(&self) -> &UnsignedLongInt{
-        self.modulo
+        &self.modulo
+    }
+
+    pub fn reducer(&self) -> &R {
+        &self.reducer
+    }
+
+    /// id unique to this context in the running process
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// whether numbers of `other` can be mixed with numbers of this context:
+    /// either it is the same context, or it has the same modulo
+    pub fn is_compatible(&self, other: &ModuloContext<R>) -> bool {
+        self.id == other.id || self.modulo == other.modulo
     }
-    pub fn add(&'m self, a: &ModuloUint, b: &ModuloUint) -> ModuloUint<'m> {
+
+    /// makes sure all of `values` were taken by this context (or a compatible one)
+    pub fn check_context(&self, values: &[&ModuloUint<R>]) -> Result<(), ContextMismatchError> {
+        if values.iter().all(|v| self.is_compatible(v.context)) {
+            Ok(())
+        } else {
+            Err(ContextMismatchError)
+        }
+    }
+
+    /// panics in debug builds if numbers from an incompatible context are used
+    fn debug_check_context(&self, values: &[&ModuloUint<R>]) {
+        debug_assert!(self.check_context(values).is_ok(), "{}", ContextMismatchError);
+    }
+
+    pub fn add(&'m self, a: &ModuloUint<R>, b: &ModuloUint<R>) -> ModuloUint<'m, R> {
+        self.debug_check_context(&[a, b]);
         ModuloUint {
-            val: barret_reduction(&(&a.val + &b.val), &self.bp),
+            val: self.add_values(&a.val, &b.val),
             context: self,
         }
     }
 
-    pub fn mul(&'m self, a: &ModuloUint, b: &ModuloUint) -> ModuloUint<'m> {
+    pub fn mul(&'m self, a: &ModuloUint<R>, b: &ModuloUint<R>) -> ModuloUint<'m, R> {
+        self.debug_check_context(&[a, b]);
         ModuloUint {
-            val: barret_reduction(&(&a.val * &b.val), &self.bp),
+            val: self.mul_values(&a.val, &b.val),
             context: self,
         }
     }
 
-    pub fn sub(&'m self, a: &ModuloUint, b: &ModuloUint) -> ModuloUint<'m> {
-        if b.val < a.val {
-            return ModuloUint {
-                val: &a.val - &b.val,
-                context: self,
-            };
-        } else {
-            return ModuloUint {
-                val: self.modulo - (&b.val - &a.val),
-                context: self,
-            };
+    pub fn sub(&'m self, a: &ModuloUint<R>, b: &ModuloUint<R>) -> ModuloUint<'m, R> {
+        self.debug_check_context(&[a, b]);
+        ModuloUint {
+            val: self.sub_values(&a.val, &b.val),
+            context: self,
         }
     }
 
+    /// returns `-a`
+    pub fn neg(&'m self, a: &ModuloUint<R>) -> ModuloUint<'m, R> {
+        self.debug_check_context(&[a]);
+        ModuloUint {
+            val: self.neg_value(&a.val),
+            context: self,
+        }
+    }
+
+    /// returns `a * b^-1`, or `None` if `b` is not invertible
+    pub fn div(&'m self, a: &ModuloUint<R>, b: &ModuloUint<R>) -> Option<ModuloUint<'m, R>> {
+        self.inv(b).map(|binv| self.mul(a, &binv))
+    }
+
     /// returns `a mod self.modulo`
-    pub fn modulo(&'m self, a: &UnsignedLongInt) -> ModuloUint<'m> {
-        if a < &self.modulo.pow(&UnsignedLongInt::from(2)) {
-            ModuloUint { val: barret_reduction(a, &self.bp), context: self }
+    pub fn modulo(&'m self, a: &UnsignedLongInt) -> ModuloUint<'m, R> {
+        ModuloUint {
+            val: self.reduce_value(a),
+            context: self,
+        }
+    }
+
+    pub fn zero(&'m self) -> ModuloUint<'m, R> {
+        self.from_u64(0)
+    }
+
+    /// 1 mod `self.modulo` (which is zero for modulo 1)
+    pub fn one(&'m self) -> ModuloUint<'m, R> {
+        self.from_u64(1)
+    }
+
+    /// returns `a mod self.modulo`
+    pub fn from_u64(&'m self, a: u64) -> ModuloUint<'m, R> {
+        self.modulo(&UnsignedLongInt::from(a))
+    }
+
+    /// parses `s` as a number in base `radix` and reduces it, panics if `radix` is not in `2..=36`
+    pub fn from_str_radix(&'m self, s: &str, radix: u32) -> Result<ModuloUint<'m, R>, FromStrRadixError> {
+        assert!((2..=36).contains(&radix), "radix must be in 2..=36");
+        if s.is_empty() {
+            return Err(FromStrRadixError);
+        }
+
+        let mut a = UnsignedLongInt::from(0);
+        for c in s.chars() {
+            let d = c.to_digit(radix).ok_or(FromStrRadixError)?;
+            a = a.mul_single_digit(radix as u64) + UnsignedLongInt::from(d as u64);
+        }
+
+        Ok(self.modulo(&a))
+    }
+
+    /// reduces a big-endian byte string
+    pub fn from_bytes(&'m self, bytes: &[u8]) -> ModuloUint<'m, R> {
+        let digits: Vec<u64> = bytes
+            .rchunks(8)
+            .map(|chunk| chunk.iter().fold(0u64, |digit, &b| (digit << 8) | b as u64))
+            .collect();
+
+        if digits.is_empty() {
+            return self.zero();
+        }
+
+        self.modulo(&UnsignedLongInt::from(digits.as_slice()))
+    }
+
+    /// maps `a` to its residue in `[0, modulo)`, e.g. -1 to `modulo - 1`
+    pub fn from_signed(&'m self, a: &SignedLongInt) -> ModuloUint<'m, R> {
+        ModuloUint {
+            val: self.canonical(a.rem_euclid(&self.modulo)),
+            context: self,
+        }
+    }
+
+    /// wraps `a` without reduction, returns `None` unless `a < modulo`
+    pub fn from_canonical(&'m self, a: &UnsignedLongInt) -> Option<ModuloUint<'m, R>> {
+        if a < self.get_modulo() {
+            Some(ModuloUint { val: self.canonical(a.clone()), context: self })
         } else {
-            ModuloUint {
-                val: UnsignedLongInt::div(a, self.modulo).1,
-                context: self,
-            }
+            None
         }
     }
 
-    pub fn pow<T: BitOps>(&self, a: &ModuloUint, b: &T) -> ModuloUint {
-        let mut c = UnsignedLongInt::from(1);
-        let mut a = a.val.to_owned();
+    /// returns `a^-1` if `a` is invertible modulo `self.modulo`
+    pub fn inv(&'m self, a: &ModuloUint<R>) -> Option<ModuloUint<'m, R>> {
+        self.debug_check_context(&[a]);
+        self.inv_value(&a.val).map(|val| ModuloUint { val, context: self })
+    }
 
-        for i in 0..(b.get_highest_set_bit().expect("must not be 0 at this point") + 1) {
-            if b.get_bit(i) {
-                c = barret_reduction(&(&c * &a), &self.bp);
-            }
-            a = barret_reduction(&(&a * &a), &self.bp);
+    /// inverts all of `values` with a single inversion and `3(n - 1)` multiplications (Montgomery's trick)
+    pub fn batch_inverse(&'m self, values: &[ModuloUint<R>]) -> Result<Vec<ModuloUint<'m, R>>, NotInvertibleError> {
+        let values: Vec<&ModuloUint<R>> = values.iter().collect();
+        self.debug_check_context(&values);
+
+        let inverses = self.batch_inverse_values(values.iter().map(|v| &v.val))?;
+        Ok(inverses.into_iter().map(|val| ModuloUint { val, context: self }).collect())
+    }
+
+    /// same as [`ModuloContext::batch_inverse`], but replaces `values` with their inverses,
+    /// `values` are left untouched on error
+    pub fn batch_inverse_in_place(&self, values: &mut [ModuloUint<R>]) -> Result<(), NotInvertibleError> {
+        self.debug_check_context(&values.iter().collect::<Vec<_>>());
+
+        let inverses = self.batch_inverse_values(values.iter().map(|v| &v.val))?;
+        for (v, inverse) in values.iter_mut().zip(inverses) {
+            v.val = inverse;
+        }
+
+        Ok(())
+    }
+
+    /// returns `a^b` using sliding-window exponentiation with the window width picked from the length of `b`
+    pub fn pow<T: BitOps>(&self, a: &ModuloUint<R>, b: &T) -> ModuloUint<'_, R> {
+        self.debug_check_context(&[a]);
+        ModuloUint {
+            val: self.pow_value(&a.val, b),
+            context: self,
+        }
+    }
+
+    /// returns `a_1^e_1 * ... * a_n^e_n` sharing the squarings between all terms:
+    /// interleaved windows (Straus, Shamir's trick for two terms) for a few terms,
+    /// Pippenger's bucket method for many
+    pub fn multi_pow<T: BitOps>(&'m self, terms: &[(&ModuloUint<R>, &T)]) -> ModuloUint<'m, R> {
+        self.debug_check_context(&terms.iter().map(|(a, _)| *a).collect::<Vec<_>>());
+
+        let raw_terms: Vec<(&UnsignedLongInt, &T)> = terms.iter().map(|(a, e)| (&a.val, *e)).collect();
+        let val = if terms.len() < PIPPENGER_THRESHOLD {
+            let bits = terms.iter().filter_map(|(_, e)| e.get_highest_set_bit()).max().map_or(0, |b| b + 1);
+            // every term has its own table, so keep the windows narrower than for a single exponentiation
+            multi_pow_straus(&self.reducer, &raw_terms, window_width(bits).min(4))
+        } else {
+            let c = ((usize::BITS - terms.len().leading_zeros()) as usize - 2).min(MAX_WINDOW_WIDTH);
+            multi_pow_pippenger(&self.reducer, &raw_terms, c)
+        };
+
+        ModuloUint {
+            val: self.canonical(val),
+            context: self,
+        }
+    }
+
+    /// returns `a^b` using fixed-window exponentiation with `2^k` precomputed powers
+    ///
+    /// panics unless `1 <= k <= MAX_WINDOW_WIDTH`
+    pub fn pow_k_ary<T: BitOps>(&self, a: &ModuloUint<R>, b: &T, k: usize) -> ModuloUint<'_, R> {
+        self.debug_check_context(&[a]);
+        ModuloUint {
+            val: self.canonical(pow_k_ary(&self.reducer, &a.val, b, k)),
+            context: self,
         }
+    }
+
+    /// returns `a^b` using left-to-right binary exponentiation
+    pub fn pow_left_to_right<T: BitOps>(&self, a: &ModuloUint<R>, b: &T) -> ModuloUint<'_, R> {
+        self.debug_check_context(&[a]);
+        ModuloUint {
+            val: self.canonical(pow_left_to_right(&self.reducer, &a.val, b)),
+            context: self,
+        }
+    }
 
+    /// returns `a^b` using right-to-left binary exponentiation
+    pub fn pow_right_to_left<T: BitOps>(&self, a: &ModuloUint<R>, b: &T) -> ModuloUint<'_, R> {
+        self.debug_check_context(&[a]);
         ModuloUint {
-            val: c,
+            val: self.canonical(pow_right_to_left(&self.reducer, &a.val, b)),
             context: self,
         }
     }
+
+    // arithmetic on plain residues, shared by the borrowed and the owned number types
+
+    /// validates that `val` lies in `[0, modulo)` in debug builds or with the `invariant-checks` feature
+    fn canonical(&self, val: UnsignedLongInt) -> UnsignedLongInt {
+        if cfg!(any(debug_assertions, feature = "invariant-checks")) {
+            assert!(&val < self.get_modulo(), "result {} is not reduced modulo {}", val, self.modulo);
+        }
+
+        val
+    }
+
+    fn add_values(&self, a: &UnsignedLongInt, b: &UnsignedLongInt) -> UnsignedLongInt {
+        if let Some(w) = self.reducer.as_word() {
+            return self.canonical(UnsignedLongInt::from(w.add_words(low_word(a), low_word(b))));
+        }
+
+        self.canonical(self.reducer.reduce(&(a + b)))
+    }
+
+    fn mul_values(&self, a: &UnsignedLongInt, b: &UnsignedLongInt) -> UnsignedLongInt {
+        if let Some(w) = self.reducer.as_word() {
+            return self.canonical(UnsignedLongInt::from(mul_mod_word(low_word(a), low_word(b), w.get_modulo())));
+        }
+
+        self.canonical(self.reducer.reduce(&(a * b)))
+    }
+
+    fn sub_values(&self, a: &UnsignedLongInt, b: &UnsignedLongInt) -> UnsignedLongInt {
+        if let Some(w) = self.reducer.as_word() {
+            return self.canonical(UnsignedLongInt::from(w.sub_words(low_word(a), low_word(b))));
+        }
+
+        let diff = match a.checked_sub(b) {
+            Some(diff) => diff,
+            None => self.get_modulo() - (b - a),
+        };
+
+        self.canonical(diff)
+    }
+
+    fn neg_value(&self, a: &UnsignedLongInt) -> UnsignedLongInt {
+        self.sub_values(&UnsignedLongInt::from(0), a)
+    }
+
+    fn reduce_value(&self, a: &UnsignedLongInt) -> UnsignedLongInt {
+        if let Some(r) = self.reducer.as_power_of_two() {
+            return self.canonical(r.reduce(a));
+        }
+        if let Some(w) = self.reducer.as_word() {
+            return self.canonical(UnsignedLongInt::from(rem_word(a, w.get_modulo())));
+        }
+
+        let n = significant_digits(&self.modulo).len();
+        let digits = significant_digits(a);
+
+        // an input of at most 2n - 2 digits is below m^2 >= BASE^(2n - 2)
+        if digits.len() + 2 <= 2 * n {
+            return self.canonical(self.reducer.reduce(a));
+        }
+        if n == 1 {
+            return self.canonical(UnsignedLongInt::from(rem_word(a, self.modulo.digits()[0])));
+        }
+
+        // Horner's scheme over n-digit chunks: every chunk is below BASE^n <= m^2,
+        // and so is the previous remainder times BASE^n mod m
+        let mut r = UnsignedLongInt::from(0);
+        for chunk in digits.chunks(n).rev() {
+            let r_shifted = self.reducer.reduce(&(&r * &self.fold));
+            r = self.add_values(&r_shifted, &self.reducer.reduce(&UnsignedLongInt::from(chunk)));
+        }
+
+        self.canonical(r)
+    }
+
+    fn inv_value(&self, a: &UnsignedLongInt) -> Option<UnsignedLongInt> {
+        if let Some(r) = self.reducer.as_power_of_two() {
+            return r.inv(a).map(|val| self.canonical(val));
+        }
+        if let Some(w) = self.reducer.as_word() {
+            return w.inv_word(low_word(a)).map(|val| self.canonical(UnsignedLongInt::from(val)));
+        }
+
+        mod_inverse(a, &self.modulo).map(|val| self.canonical(val))
+    }
+
+    fn batch_inverse_values<'a, I>(&self, values: I) -> Result<Vec<UnsignedLongInt>, NotInvertibleError>
+    where
+        I: Iterator<Item = &'a UnsignedLongInt>,
+    {
+        let values: Vec<&UnsignedLongInt> = values.collect();
+        if values.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // prefix[i] = values[0] * ... * values[i]
+        let mut prefix = Vec::with_capacity(values.len());
+        prefix.push(values[0].clone());
+        for i in 1..values.len() {
+            prefix.push(self.mul_values(&prefix[i - 1], values[i]));
+        }
+
+        // the product is invertible iff every factor is, look for the culprit otherwise
+        let mut inv = match self.inv_value(&prefix[values.len() - 1]) {
+            Some(inv) => inv,
+            None => {
+                let index = values.iter().position(|v| self.inv_value(v).is_none()).unwrap_or(0);
+                return Err(NotInvertibleError { index });
+            }
+        };
+
+        let mut inverses = vec![UnsignedLongInt::from(0); values.len()];
+        for i in (1..values.len()).rev() {
+            inverses[i] = self.mul_values(&inv, &prefix[i - 1]);
+            inv = self.mul_values(&inv, values[i]);
+        }
+        inverses[0] = inv;
+
+        Ok(inverses)
+    }
+
+    fn pow_value<T: BitOps>(&self, a: &UnsignedLongInt, b: &T) -> UnsignedLongInt {
+        if let Some(w) = self.reducer.as_word() {
+            return self.canonical(UnsignedLongInt::from(w.pow_word(low_word(a), b)));
+        }
+
+        let k = window_width(b.get_highest_set_bit().map_or(0, |bit| bit + 1));
+        self.canonical(pow_sliding_window(&self.reducer, a, b, k))
+    }
+}
+
+/// returns `BASE^n mod modulo` for an `n`-digit `modulo`
+fn fold_factor(modulo: &UnsignedLongInt) -> UnsignedLongInt {
+    let n = significant_digits(modulo).len();
+    UnsignedLongInt::from(1).shl_digits(n).div(modulo).1
+}
+
+/// the only digit of a residue modulo a one-digit modulus
+fn low_word(a: &UnsignedLongInt) -> u64 {
+    a.digits().first().copied().unwrap_or(0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::BarretParameters;
     use std::error::Error;
     use std::str::FromStr;
 
@@ -178,6 +671,307 @@ mod tests {
 
         assert_eq!(result, expected);
 
+        for result in [mc.pow_right_to_left(&amod, &b), mc.pow_left_to_right(&amod, &b), mc.pow_k_ary(&amod, &b, 4)] {
+            let result: UnsignedLongInt = result.into();
+            assert_eq!(result, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn mod_inv() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784ee7a0b2df68cd23a1e5e01fe37626dc77d4cd7e8d1da5365ca90ed72529f3952f")?;
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+
+        let mc = ModuloContext::new(&m);
+        let amod = mc.modulo(&a);
+        let ainv = mc.inv(&amod).expect("a must be invertible");
+
+        let result: UnsignedLongInt = mc.mul(&amod, &ainv).into();
+        assert_eq!(result, UnsignedLongInt::from(1));
+
+        let zero = mc.zero();
+        assert!(mc.inv(&zero).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn mod_div_neg() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str("4b11e668e24d513ec96654975ce04ca09f3eeea20501df88edba0eedce6814a2562fb8fe4f6ef5588aa704c65892813d151c1766922556238687d7c54f15c0c6")?;
+        let b = UnsignedLongInt::from_str("3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f1353ff5ff9c1a640df60811d70c202950c4c7f9c566807f17e0fca271456f001125")?;
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+
+        let mc = ModuloContext::new(&m);
+        let quotient = mc.modulo(&a) / mc.modulo(&b);
+        let result: UnsignedLongInt = (quotient * mc.modulo(&b)).into();
+        assert_eq!(result, mc.modulo(&a).into());
+
+        let result: UnsignedLongInt = (-mc.modulo(&a) + mc.modulo(&a)).into();
+        assert_eq!(result, UnsignedLongInt::from(0));
+        let result: UnsignedLongInt = (-mc.zero()).into();
+        assert_eq!(result, UnsignedLongInt::from(0));
+
+        assert!(mc.modulo(&a).checked_div(&mc.modulo(&m)).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn context_check() -> Result<(), Box<dyn Error>> {
+        let m1 = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+        let m2 = UnsignedLongInt::from_str("0b8c9870a515714526f4a3731f6b6dda")?;
+
+        let mc1 = ModuloContext::new(&m1);
+        let mc1_again = ModuloContext::new(&m1);
+        let mc2 = ModuloContext::new(&m2);
+        let a = mc1.modulo(&UnsignedLongInt::from(5));
+        let b = mc1_again.modulo(&UnsignedLongInt::from(7));
+        let c = mc2.modulo(&UnsignedLongInt::from(9));
+
+        assert_ne!(mc1.id(), mc1_again.id());
+        assert_eq!(mc1.check_context(&[&a, &b]), Ok(()));
+        assert_eq!(mc1.check_context(&[&a, &c]), Err(ContextMismatchError));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "different modulo")]
+    fn context_mismatch_panics() {
+        let m1 = UnsignedLongInt::from(1000003);
+        let m2 = UnsignedLongInt::from(1000033);
+
+        let mc1 = ModuloContext::new(&m1);
+        let mc2 = ModuloContext::new(&m2);
+
+        let _ = mc1.modulo(&UnsignedLongInt::from(5)) + mc2.modulo(&UnsignedLongInt::from(7));
+    }
+
+    #[test]
+    fn mod_pow_methods() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784ee7a0b2df68cd23a1e5e01fe37626dc77d4cd7e8d1da5365ca90ed72529f3952f")?;
+        let m = UnsignedLongInt::from_str("0b8c9870a515714526f4a3731f6b6dda")?;
+
+        let mc = ModuloContext::new(&m);
+        let amod = mc.modulo(&a);
+
+        let cube = mc.mul(&mc.mul(&amod, &amod), &amod);
+        assert_eq!(amod.pow_u64(3), cube);
+        assert_eq!(amod.pow(&UnsignedLongInt::from(3)), cube);
+
+        Ok(())
+    }
+
+    #[test]
+    fn standard_traits() -> Result<(), Box<dyn Error>> {
+        let m1 = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+        let m2 = UnsignedLongInt::from_str("0b8c9870a515714526f4a3731f6b6dda")?;
+        let mc1 = ModuloContext::new(&m1);
+        let mc1_again = ModuloContext::new(&m1);
+        let mc2 = ModuloContext::new(&m2);
+
+        let a = mc1.modulo(&UnsignedLongInt::from(5));
+        assert_eq!(a.clone(), a);
+        assert_eq!(a, mc1_again.modulo(&UnsignedLongInt::from(5)));
+        assert_ne!(a, mc2.modulo(&UnsignedLongInt::from(5)));
+        assert_eq!(a.to_string(), "0x5");
+        assert_eq!(a.value(), &UnsignedLongInt::from(5));
+
+        assert!(mc1.modulo(&m1).is_zero());
+        assert!(mc1.modulo(&(&m1 + &UnsignedLongInt::from(1))).is_one());
+        assert!(!a.is_zero() && !a.is_one());
+
+        let values: Vec<_> = (1..=5).map(|v| mc1.modulo(&UnsignedLongInt::from(v))).collect();
+        assert_eq!(values.iter().sum::<ModuloUint>(), mc1.modulo(&UnsignedLongInt::from(15)));
+        assert_eq!(values.into_iter().product::<ModuloUint>(), mc1.modulo(&UnsignedLongInt::from(120)));
+
+        let set: std::collections::HashSet<_> = [a.clone(), a.clone(), mc1.modulo(&UnsignedLongInt::from(6))].into_iter().collect();
+        assert_eq!(set.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "empty iterator")]
+    fn empty_sum_panics() {
+        let _: ModuloUint = std::iter::empty::<ModuloUint>().sum();
+    }
+
+    #[test]
+    fn constructors() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+        let mc = ModuloContext::new(&m);
+
+        assert!(mc.zero().is_zero());
+        assert!(mc.one().is_one());
+        assert_eq!(mc.from_u64(12345), mc.modulo(&UnsignedLongInt::from(12345)));
+
+        assert_eq!(mc.from_str_radix("12345", 10)?, mc.from_u64(12345));
+        assert_eq!(mc.from_str_radix("-1", 10), Err(FromStrRadixError));
+        assert_eq!(mc.from_str_radix("", 10), Err(FromStrRadixError));
+        let big = "ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784ee7a0b2df68cd23a1e5e01fe37626dc77d4cd7e8d1da5365ca90ed72529f3952f";
+        assert_eq!(mc.from_str_radix(big, 16)?, mc.modulo(&UnsignedLongInt::from_str(big)?));
+
+        assert_eq!(mc.from_bytes(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09]), mc.from_str_radix("10203040506070809", 16)?);
+        assert!(mc.from_bytes(&[]).is_zero());
+
+        let minus_one = mc.from_signed(&SignedLongInt::from(-1));
+        assert_eq!(minus_one.value(), &(&m - &UnsignedLongInt::from(1)));
+        assert_eq!(mc.from_signed(&SignedLongInt::from(7)), mc.from_u64(7));
+
+        assert!(mc.from_canonical(&UnsignedLongInt::from(7)).is_some());
+        assert!(mc.from_canonical(&m).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn sub_equal_values() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+        let mc = ModuloContext::new(&m);
+        let a = mc.from_str_radix("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca677", 16)?;
+
+        assert!(mc.sub(&a, &a).is_zero());
+        assert!(mc.sub(&mc.zero(), &mc.zero()).is_zero());
+        assert_eq!(mc.sub(&mc.zero(), &mc.one()).value(), &(&m - &UnsignedLongInt::from(1)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn zero_exponent() {
+        let m = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&m);
+        let a = mc.from_u64(12345);
+        let zero = UnsignedLongInt::from(0);
+
+        for result in [mc.pow(&a, &zero), mc.pow_right_to_left(&a, &zero), mc.pow_left_to_right(&a, &zero), mc.pow_k_ary(&a, &zero, 4)] {
+            assert!(result.is_one());
+        }
+        assert!(mc.zero().pow(&zero).is_one());
+    }
+
+    #[test]
+    fn modulo_one() {
+        let m = UnsignedLongInt::from(1);
+        let mc = ModuloContext::new(&m);
+        let a = mc.from_u64(12345);
+
+        assert!(a.is_zero());
+        assert!(mc.one().is_zero());
+        assert!((&a + &a).is_zero());
+        assert!(mc.sub(&a, &mc.one()).is_zero());
+        assert!(mc.mul(&a, &a).is_zero());
+        assert!((-a.clone()).is_zero());
+        assert!(a.pow_u64(0).is_zero());
+        assert!(mc.pow_right_to_left(&a, &UnsignedLongInt::from(0)).is_zero());
+        assert!(a.pow_u64(5).is_zero());
+    }
+
+    #[test]
+    fn fold_long_inputs() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str(&"ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784e".repeat(12))?;
+        let moduli = [
+            "463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b",
+            "1000000000000000f",
+            "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed",
+            "f4243",
+        ];
+
+        for m in moduli {
+            let m = UnsignedLongInt::from_str(m)?;
+            let expected = UnsignedLongInt::div(&a, &m).1;
+
+            let mc = ModuloContext::new(&m);
+            assert_eq!(mc.modulo(&a).value(), &expected);
+            let generic = ModuloContext::with_reducer(&m, BarretParameters::new(&m, m.num_digits()));
+            assert_eq!(generic.modulo(&a).value(), &expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn power_of_two_modulo() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784ee7a0b2df68cd23a1e5e01fe37626dc77d4cd7e8d1da5365ca90ed72529f3952f")?;
+        let b = UnsignedLongInt::from_str("3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f1353ff5ff9c1a640df60811d70c202950c4c7f9c566807f17e0fca271456f001125")?;
+
+        for k in [0, 1, 13, 64, 65, 127, 200, 256] {
+            let mut m = UnsignedLongInt::from(0);
+            m.set_bit(k);
+            let mc = ModuloContext::new(&m);
+            assert!(matches!(mc.reducer(), AutoReducer::PowerOfTwo(_)));
+            let generic = ModuloContext::with_reducer(&m, BarretParameters::new(&m, m.num_digits()));
+
+            let (amod, bmod) = (mc.modulo(&a), mc.modulo(&b));
+            let (ga, gb) = (generic.modulo(&a), generic.modulo(&b));
+            assert_eq!(amod.value(), ga.value());
+            assert_eq!((&amod + &bmod).value(), generic.add(&ga, &gb).value());
+            assert_eq!(mc.sub(&bmod, &amod).value(), generic.sub(&gb, &ga).value());
+            assert_eq!(mc.mul(&amod, &bmod).value(), generic.mul(&ga, &gb).value());
+            assert_eq!(amod.pow(&b).value(), generic.pow(&ga, &b).value());
+
+            // both numbers are odd, even ones have no inverse
+            let ainv = mc.inv(&amod).expect("odd numbers are invertible");
+            assert_eq!(ainv.value(), generic.inv(&ga).expect("odd numbers are invertible").value());
+            assert!(mc.mul(&ainv, &amod).is_one() || k == 0);
+            assert_eq!(mc.inv(&mc.from_u64(2)).is_none(), k > 0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn batch_inverse() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+        let mc = ModuloContext::new(&m);
+        let values: Vec<_> = (1..20).map(|v| mc.from_u64(v * 0x1234567)).collect();
+
+        let inverses = mc.batch_inverse(&values)?;
+        for (v, inverse) in values.iter().zip(&inverses) {
+            assert_eq!(Some(inverse.clone()), mc.inv(v));
+        }
+        assert!(mc.batch_inverse(&[])?.is_empty());
+
+        let mut in_place = values.clone();
+        mc.batch_inverse_in_place(&mut in_place)?;
+        assert_eq!(in_place, inverses);
+
+        Ok(())
+    }
+
+    #[test]
+    fn batch_inverse_error() {
+        let m = UnsignedLongInt::from(1000);
+        let mc = ModuloContext::new(&m);
+        let mut values: Vec<_> = [3, 7, 10, 11, 5].into_iter().map(|v| mc.from_u64(v)).collect();
+
+        assert_eq!(mc.batch_inverse(&values), Err(NotInvertibleError { index: 2 }));
+        let original = values.clone();
+        assert_eq!(mc.batch_inverse_in_place(&mut values), Err(NotInvertibleError { index: 2 }));
+        assert_eq!(values, original);
+    }
+
+    #[test]
+    fn multi_pow() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+        let mc = ModuloContext::new(&m);
+        let e = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784e")?;
+
+        for n in [1u64, 2, 5, 40] {
+            let bases: Vec<_> = (1..=n).map(|i| mc.from_u64(i.wrapping_mul(0x9e3779b97f4a7c15))).collect();
+            let exponents: Vec<_> = (1..=n).map(|i| &e - &UnsignedLongInt::from(i * i)).collect();
+            let terms: Vec<_> = bases.iter().zip(&exponents).collect();
+
+            let expected = terms.iter().map(|(a, e)| a.pow(*e)).product::<ModuloUint>();
+            assert_eq!(mc.multi_pow(&terms), expected);
+        }
+
+        assert!(mc.multi_pow::<UnsignedLongInt>(&[]).is_one());
+
         Ok(())
     }
 }
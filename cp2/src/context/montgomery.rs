@@ -0,0 +1,229 @@
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::{BitOps, EvenOdd};
+use crate::reduction::Reducer;
+
+/// number in Montgomery form, i.e. `a * R mod m` for `R = BASE^n`
+pub struct MontgomeryUint<'c> {
+    val: UnsignedLongInt,
+    context: &'c MontgomeryContext<'c>,
+}
+
+impl MontgomeryUint<'_> {
+    pub fn context(&self) -> &MontgomeryContext<'_> {
+        self.context
+    }
+}
+
+impl From<MontgomeryUint<'_>> for UnsignedLongInt {
+    /// converts the number out of Montgomery form
+    fn from(value: MontgomeryUint<'_>) -> Self {
+        value.context.redc(&value.val)
+    }
+}
+
+/// context for modular calculations with Montgomery reduction (REDC), works with odd moduli only
+pub struct MontgomeryContext<'m> {
+    modulo: &'m UnsignedLongInt,
+    /// number of digits in `R = BASE^n`
+    n: usize,
+    /// `-modulo^-1 mod BASE`
+    m_inv: u64,
+    /// `R mod modulo`, i.e. 1 in Montgomery form
+    r1: UnsignedLongInt,
+    /// `R^2 mod modulo`, used for conversion into Montgomery form
+    r2: UnsignedLongInt,
+}
+
+impl<'m> MontgomeryContext<'m> {
+    /// constructs new context for Montgomery multiplication, returns `None` for even moduli
+    pub fn new(modulo: &'m UnsignedLongInt) -> Option<Self> {
+        if modulo.is_even() {
+            return None;
+        }
+
+        let n = modulo.num_digits();
+        let r2 = UnsignedLongInt::div(&UnsignedLongInt::from(1).shl_digits(2 * n), modulo).1;
+
+        let mut ctx = Self {
+            modulo,
+            n,
+            m_inv: Self::calculate_m_inv(modulo.digits()[0]),
+            r1: UnsignedLongInt::from(0),
+            r2,
+        };
+        ctx.r1 = ctx.redc(&ctx.r2);
+
+        Some(ctx)
+    }
+
+    /// calculate `-m^-1 mod 2^64` with Newton iteration, each step doubles the number of correct bits
+    fn calculate_m_inv(m0: u64) -> u64 {
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(m0.wrapping_mul(inv)));
+        }
+
+        inv.wrapping_neg()
+    }
+
+    /// returns `t * R^-1 mod self.modulo` for `t < self.modulo * R`
+    fn redc(&self, t: &UnsignedLongInt) -> UnsignedLongInt {
+        let n = self.n;
+        let m = self.modulo.digits();
+        let mut a = vec![0u64; 2 * n + 1];
+        a[..t.num_digits()].copy_from_slice(t.digits());
+
+        // add multiples of `m` digit by digit until the lower `n` digits are zeroes
+        for i in 0..n {
+            let u = a[i].wrapping_mul(self.m_inv) as u128;
+            let mut carry = 0u128;
+            for j in 0..n {
+                let s = a[i + j] as u128 + u * m[j] as u128 + carry;
+                a[i + j] = s as u64;
+                carry = s >> 64;
+            }
+
+            let mut k = i + n;
+            while carry != 0 {
+                let s = a[k] as u128 + carry;
+                a[k] = s as u64;
+                carry = s >> 64;
+                k += 1;
+            }
+        }
+
+        let r = UnsignedLongInt::from(&a[n..]);
+        r.checked_sub(self.modulo).unwrap_or(r)
+    }
+
+    pub fn get_modulo(&self) -> &UnsignedLongInt {
+        self.modulo
+    }
+
+    /// returns `a mod self.modulo` converted into Montgomery form
+    pub fn modulo(&'m self, a: &UnsignedLongInt) -> MontgomeryUint<'m> {
+        let val = if a < self.modulo {
+            self.redc(&(a * &self.r2))
+        } else {
+            self.redc(&(UnsignedLongInt::div(a, self.modulo).1 * &self.r2))
+        };
+
+        MontgomeryUint { val, context: self }
+    }
+
+    pub fn add(&'m self, a: &MontgomeryUint, b: &MontgomeryUint) -> MontgomeryUint<'m> {
+        let sum = &a.val + &b.val;
+        MontgomeryUint {
+            val: sum.checked_sub(self.modulo).unwrap_or(sum),
+            context: self,
+        }
+    }
+
+    pub fn sub(&'m self, a: &MontgomeryUint, b: &MontgomeryUint) -> MontgomeryUint<'m> {
+        let val = match a.val.checked_sub(&b.val) {
+            Some(diff) => diff,
+            None => self.modulo - (&b.val - &a.val),
+        };
+
+        MontgomeryUint { val, context: self }
+    }
+
+    pub fn mul(&'m self, a: &MontgomeryUint, b: &MontgomeryUint) -> MontgomeryUint<'m> {
+        MontgomeryUint {
+            val: self.redc(&(&a.val * &b.val)),
+            context: self,
+        }
+    }
+
+    pub fn pow<T: BitOps>(&'m self, a: &MontgomeryUint, b: &T) -> MontgomeryUint<'m> {
+        let mut c = self.r1.clone();
+        let mut a = a.val.to_owned();
+
+        if let Some(highest_bit) = b.get_highest_set_bit() {
+            for i in 0..(highest_bit + 1) {
+                if b.get_bit(i) {
+                    c = self.redc(&(&c * &a));
+                }
+                a = self.redc(&(&a * &a));
+            }
+        }
+
+        MontgomeryUint { val: c, context: self }
+    }
+}
+
+impl Reducer for MontgomeryContext<'_> {
+    fn modulus(&self) -> &UnsignedLongInt {
+        self.modulo
+    }
+
+    /// values outside of Montgomery form are reduced as `REDC(REDC(x) * R^2)`,
+    /// use [`MontgomeryContext`] directly to keep long chains in Montgomery form
+    fn reduce(&self, x: &UnsignedLongInt) -> UnsignedLongInt {
+        self.redc(&(self.redc(x) * &self.r2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ModuloContext;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    const A: &str = "ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784ee7a0b2df68cd23a1e5e01fe37626dc77d4cd7e8d1da5365ca90ed72529f3952f";
+    const B: &str = "3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f1353ff5ff9c1a640df60811d70c202950c4c7f9c566807f17e0fca271456f001125";
+    const M: &str = "463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b";
+
+    #[test]
+    fn even_modulo() {
+        assert!(MontgomeryContext::new(&UnsignedLongInt::from(0x1000)).is_none());
+    }
+
+    #[test]
+    fn conversion() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str(A)?;
+        let m = UnsignedLongInt::from_str(M)?;
+
+        let mc = MontgomeryContext::new(&m).expect("modulo is odd");
+        let result: UnsignedLongInt = mc.modulo(&a).into();
+
+        assert_eq!(result, UnsignedLongInt::div(&a, &m).1);
+        Ok(())
+    }
+
+    #[test]
+    fn matches_barret() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str(A)?;
+        let b = UnsignedLongInt::from_str(B)?;
+        let m = UnsignedLongInt::from_str(M)?;
+
+        let mc = MontgomeryContext::new(&m).expect("modulo is odd");
+        let bc = ModuloContext::new(&m);
+        let (amont, bmont) = (mc.modulo(&a), mc.modulo(&b));
+        let (amod, bmod) = (bc.modulo(&a), bc.modulo(&b));
+
+        let result: UnsignedLongInt = mc.add(&amont, &bmont).into();
+        assert_eq!(result, bc.add(&amod, &bmod).into());
+        let result: UnsignedLongInt = mc.sub(&amont, &bmont).into();
+        assert_eq!(result, bc.sub(&amod, &bmod).into());
+        let result: UnsignedLongInt = mc.mul(&amont, &bmont).into();
+        assert_eq!(result, bc.mul(&amod, &bmod).into());
+        let result: UnsignedLongInt = mc.pow(&amont, &b).into();
+        assert_eq!(result, bc.pow(&amod, &b).into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn pow_zero() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str(A)?;
+        let m = UnsignedLongInt::from_str(M)?;
+
+        let mc = MontgomeryContext::new(&m).expect("modulo is odd");
+        let result: UnsignedLongInt = mc.pow(&mc.modulo(&a), &UnsignedLongInt::from(0)).into();
+
+        assert_eq!(result, UnsignedLongInt::from(1));
+        Ok(())
+    }
+}
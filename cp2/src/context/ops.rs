@@ -1,27 +1,45 @@
 use std::ops::*;
 use crate::context::ModuloUint;
+use crate::reduction::Reducer;
 
 #[auto_impl_ops::auto_ops]
-impl<'s, 'd> Add<ModuloUint<'d>> for ModuloUint<'s> where 'd: 's
+impl<'s, 'd, R: Reducer> Add<ModuloUint<'d, R>> for ModuloUint<'s, R> where 'd: 's
 {
-    type Output = ModuloUint<'s>;
-    fn add(self, rhs: ModuloUint<'d>) -> Self::Output {
+    type Output = ModuloUint<'s, R>;
+    fn add(self, rhs: ModuloUint<'d, R>) -> Self::Output {
         self.context.add(&self, &rhs)
     }
 }
 
-impl<'s, 'd> Sub<ModuloUint<'d>> for ModuloUint<'s> where 'd: 's
+impl<'s, 'd, R: Reducer> Sub<ModuloUint<'d, R>> for ModuloUint<'s, R> where 'd: 's
 {
-    type Output = ModuloUint<'s>;
-    fn sub(self, rhs: ModuloUint<'d>) -> Self::Output {
+    type Output = ModuloUint<'s, R>;
+    fn sub(self, rhs: ModuloUint<'d, R>) -> Self::Output {
         self.context.sub(&self, &rhs)
     }
 }
 
-impl<'s, 'd> Mul<ModuloUint<'d>> for ModuloUint<'s> where 'd: 's
+impl<'s, 'd, R: Reducer> Mul<ModuloUint<'d, R>> for ModuloUint<'s, R> where 'd: 's
 {
-    type Output = ModuloUint<'s>;
-    fn mul(self, rhs: ModuloUint<'d>) -> Self::Output {
+    type Output = ModuloUint<'s, R>;
+    fn mul(self, rhs: ModuloUint<'d, R>) -> Self::Output {
         self.context.mul(&self, &rhs)
     }
-}
\ No newline at end of file
+}
+
+impl<'s, 'd, R: Reducer> Div<ModuloUint<'d, R>> for ModuloUint<'s, R> where 'd: 's
+{
+    type Output = ModuloUint<'s, R>;
+    /// panics if `rhs` is not invertible, see [`ModuloUint::checked_div`]
+    fn div(self, rhs: ModuloUint<'d, R>) -> Self::Output {
+        self.context.div(&self, &rhs).expect("divisor must be invertible")
+    }
+}
+
+impl<'s, R: Reducer> Neg for ModuloUint<'s, R>
+{
+    type Output = ModuloUint<'s, R>;
+    fn neg(self) -> Self::Output {
+        self.context.neg(&self)
+    }
+}
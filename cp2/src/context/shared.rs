@@ -0,0 +1,263 @@
+use std::ops::*;
+use std::sync::Arc;
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::BitOps;
+use crate::context::{ContextMismatchError, ModuloContext};
+use crate::reduction::{AutoReducer, Reducer};
+
+/// number modulo the modulus of a [`SharedModuloContext`], owns a handle to its context
+/// and thus can be returned from functions, stored in structs and sent between threads
+pub struct SharedModuloUint<R = AutoReducer> {
+    val: UnsignedLongInt,
+    context: SharedModuloContext<R>,
+}
+
+impl<R> Clone for SharedModuloUint<R> {
+    fn clone(&self) -> Self {
+        Self { val: self.val.clone(), context: self.context.clone() }
+    }
+}
+
+impl<R: Reducer> SharedModuloUint<R> {
+    pub fn context(&self) -> &SharedModuloContext<R> {
+        &self.context
+    }
+
+    /// returns `self / rhs`, or `None` if `rhs` is not invertible
+    pub fn checked_div(&self, rhs: &SharedModuloUint<R>) -> Option<SharedModuloUint<R>> {
+        self.context.div(self, rhs)
+    }
+
+    /// returns `self^e`
+    pub fn pow<T: BitOps>(&self, e: &T) -> SharedModuloUint<R> {
+        self.context.pow(self, e)
+    }
+
+    /// returns `self^e`
+    pub fn pow_u64(&self, e: u64) -> SharedModuloUint<R> {
+        self.context.pow(self, &UnsignedLongInt::from(e))
+    }
+}
+
+impl<R> From<SharedModuloUint<R>> for UnsignedLongInt {
+    fn from(value: SharedModuloUint<R>) -> Self {
+        value.val
+    }
+}
+
+impl<R> BitOps for SharedModuloUint<R> {
+    fn get_bit(&self, i: usize) -> bool {
+        self.val.get_bit(i)
+    }
+    fn get_highest_set_bit(&self) -> Option<usize> {
+        self.val.get_highest_set_bit()
+    }
+}
+
+/// owned counterpart of [`ModuloContext`]: the modulus and reduction parameters live behind an `Arc`,
+/// so cloning the context (and every number made by it) only bumps a reference counter
+pub struct SharedModuloContext<R = AutoReducer> {
+    inner: Arc<ModuloContext<'static, R>>,
+}
+
+impl<R> Clone for SharedModuloContext<R> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl SharedModuloContext {
+    /// constucts new shared context for modular calculations, the reducer is picked from the shape of `modulo`
+    pub fn new(modulo: UnsignedLongInt) -> Self {
+        Self::from_context(ModuloContext::new_owned(modulo))
+    }
+}
+
+impl<R: Reducer> SharedModuloContext<R> {
+    pub fn from_context(context: ModuloContext<'static, R>) -> Self {
+        Self { inner: Arc::new(context) }
+    }
+
+    /// borrowed context for local calculations with [`crate::context::ModuloUint`]
+    pub fn as_context(&self) -> &ModuloContext<'static, R> {
+        &self.inner
+    }
+
+    pub fn get_modulo(&self) -> &UnsignedLongInt {
+        self.inner.get_modulo()
+    }
+
+    /// makes sure all of `values` were taken by this context (or a compatible one)
+    pub fn check_context(&self, values: &[&SharedModuloUint<R>]) -> Result<(), ContextMismatchError> {
+        if values.iter().all(|v| self.inner.is_compatible(&v.context.inner)) {
+            Ok(())
+        } else {
+            Err(ContextMismatchError)
+        }
+    }
+
+    fn debug_check_context(&self, values: &[&SharedModuloUint<R>]) {
+        debug_assert!(self.check_context(values).is_ok(), "{}", ContextMismatchError);
+    }
+
+    fn wrap(&self, val: UnsignedLongInt) -> SharedModuloUint<R> {
+        SharedModuloUint { val, context: self.clone() }
+    }
+
+    pub fn add(&self, a: &SharedModuloUint<R>, b: &SharedModuloUint<R>) -> SharedModuloUint<R> {
+        self.debug_check_context(&[a, b]);
+        self.wrap(self.inner.add_values(&a.val, &b.val))
+    }
+
+    pub fn mul(&self, a: &SharedModuloUint<R>, b: &SharedModuloUint<R>) -> SharedModuloUint<R> {
+        self.debug_check_context(&[a, b]);
+        self.wrap(self.inner.mul_values(&a.val, &b.val))
+    }
+
+    pub fn sub(&self, a: &SharedModuloUint<R>, b: &SharedModuloUint<R>) -> SharedModuloUint<R> {
+        self.debug_check_context(&[a, b]);
+        self.wrap(self.inner.sub_values(&a.val, &b.val))
+    }
+
+    /// returns `-a`
+    pub fn neg(&self, a: &SharedModuloUint<R>) -> SharedModuloUint<R> {
+        self.debug_check_context(&[a]);
+        self.wrap(self.inner.neg_value(&a.val))
+    }
+
+    /// returns `a * b^-1`, or `None` if `b` is not invertible
+    pub fn div(&self, a: &SharedModuloUint<R>, b: &SharedModuloUint<R>) -> Option<SharedModuloUint<R>> {
+        self.inv(b).map(|binv| self.mul(a, &binv))
+    }
+
+    /// returns `a mod self.modulo`
+    pub fn modulo(&self, a: &UnsignedLongInt) -> SharedModuloUint<R> {
+        self.wrap(self.inner.reduce_value(a))
+    }
+
+    /// returns `a^-1` if `a` is invertible modulo `self.modulo`
+    pub fn inv(&self, a: &SharedModuloUint<R>) -> Option<SharedModuloUint<R>> {
+        self.debug_check_context(&[a]);
+        self.inner.inv_value(&a.val).map(|val| self.wrap(val))
+    }
+
+    /// returns `a^b`, see [`ModuloContext::pow`]
+    pub fn pow<T: BitOps>(&self, a: &SharedModuloUint<R>, b: &T) -> SharedModuloUint<R> {
+        self.debug_check_context(&[a]);
+        self.wrap(self.inner.pow_value(&a.val, b))
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<R: Reducer> Add<&SharedModuloUint<R>> for &SharedModuloUint<R>
+{
+    type Output = SharedModuloUint<R>;
+    fn add(self, rhs: &SharedModuloUint<R>) -> Self::Output {
+        self.context.add(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<R: Reducer> Sub<&SharedModuloUint<R>> for &SharedModuloUint<R>
+{
+    type Output = SharedModuloUint<R>;
+    fn sub(self, rhs: &SharedModuloUint<R>) -> Self::Output {
+        self.context.sub(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<R: Reducer> Mul<&SharedModuloUint<R>> for &SharedModuloUint<R>
+{
+    type Output = SharedModuloUint<R>;
+    fn mul(self, rhs: &SharedModuloUint<R>) -> Self::Output {
+        self.context.mul(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<R: Reducer> Div<&SharedModuloUint<R>> for &SharedModuloUint<R>
+{
+    type Output = SharedModuloUint<R>;
+    /// panics if `rhs` is not invertible, see [`SharedModuloUint::checked_div`]
+    fn div(self, rhs: &SharedModuloUint<R>) -> Self::Output {
+        self.context.div(self, rhs).expect("divisor must be invertible")
+    }
+}
+
+impl<R: Reducer> Neg for SharedModuloUint<R>
+{
+    type Output = SharedModuloUint<R>;
+    fn neg(self) -> Self::Output {
+        self.context.neg(&self)
+    }
+}
+
+impl<R: Reducer> Neg for &SharedModuloUint<R>
+{
+    type Output = SharedModuloUint<R>;
+    fn neg(self) -> Self::Output {
+        self.context.neg(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+    use std::thread;
+
+    const M: &str = "463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b";
+
+    fn is_send_sync<T: Send + Sync>() {}
+
+    /// values can outlive the scope that created the context
+    fn make_values() -> Result<(SharedModuloUint, SharedModuloUint), Box<dyn Error>> {
+        let mc = SharedModuloContext::new(UnsignedLongInt::from_str(M)?);
+        let a = mc.modulo(&UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784ee7a0b2df68cd23a1e5e01fe37626dc77d4cd7e8d1da5365ca90ed72529f3952f")?);
+        let b = mc.modulo(&UnsignedLongInt::from_str("3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f1353ff5ff9c1a640df60811d70c202950c4c7f9c566807f17e0fca271456f001125")?);
+
+        Ok((a, b))
+    }
+
+    #[test]
+    fn send_sync() {
+        is_send_sync::<SharedModuloContext>();
+        is_send_sync::<SharedModuloUint>();
+    }
+
+    #[test]
+    fn matches_borrowed() -> Result<(), Box<dyn Error>> {
+        let (a, b) = make_values()?;
+        let m = UnsignedLongInt::from_str(M)?;
+        let mc = ModuloContext::new(&m);
+        let (amod, bmod) = (mc.modulo(&a.clone().into()), mc.modulo(&b.clone().into()));
+
+        let result: UnsignedLongInt = (&a + &b).into();
+        assert_eq!(result, mc.add(&amod, &bmod).into());
+        let result: UnsignedLongInt = (&a - &b).into();
+        assert_eq!(result, mc.sub(&amod, &bmod).into());
+        let result: UnsignedLongInt = (&a * &b).into();
+        assert_eq!(result, mc.mul(&amod, &bmod).into());
+        let result: UnsignedLongInt = (&a / &b * &b).into();
+        assert_eq!(result, a.into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn across_threads() -> Result<(), Box<dyn Error>> {
+        let (a, b) = make_values()?;
+        let expected: UnsignedLongInt = a.pow_u64(5).into();
+
+        let handle = thread::spawn(move || a.pow_u64(5));
+        let result: UnsignedLongInt = handle.join().expect("thread must not panic").into();
+        assert_eq!(result, expected);
+
+        // the context is still alive through `b`
+        assert_eq!(b.context().get_modulo(), &UnsignedLongInt::from_str(M)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::*;
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::{inv_mod_word, mul_mod_word, rem_word, significant_digits, BitOps};
+use crate::reduction::Reducer;
+
+/// number modulo the modulus of a [`SmallModuloContext`]
+#[derive(Clone, Copy)]
+pub struct SmallModuloUint<'c> {
+    val: u64,
+    context: &'c SmallModuloContext,
+}
+
+impl<'c> SmallModuloUint<'c> {
+    pub fn value(&self) -> u64 {
+        self.val
+    }
+
+    pub fn context(&self) -> &'c SmallModuloContext {
+        self.context
+    }
+
+    /// returns `self^e`
+    pub fn pow<T: BitOps>(&self, e: &T) -> SmallModuloUint<'c> {
+        self.context.pow(self, e)
+    }
+
+    /// returns `self^e`
+    pub fn pow_u64(&self, e: u64) -> SmallModuloUint<'c> {
+        self.context.pow_u64(self, e)
+    }
+}
+
+impl From<SmallModuloUint<'_>> for u64 {
+    fn from(value: SmallModuloUint<'_>) -> Self {
+        value.val
+    }
+}
+
+impl From<SmallModuloUint<'_>> for UnsignedLongInt {
+    fn from(value: SmallModuloUint<'_>) -> Self {
+        UnsignedLongInt::from(value.val)
+    }
+}
+
+impl Debug for SmallModuloUint<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (mod {})", self.val, self.context.modulo)
+    }
+}
+
+impl Display for SmallModuloUint<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.val)
+    }
+}
+
+impl PartialEq for SmallModuloUint<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.val == other.val && self.context.modulo == other.context.modulo
+    }
+}
+
+impl Eq for SmallModuloUint<'_> {}
+
+/// context for moduli below `2^64`: numbers are plain `u64`s, products are `u128`s,
+/// and exponentiation modulo odd moduli runs in Montgomery form
+#[derive(Debug)]
+pub struct SmallModuloContext {
+    modulo: u64,
+    /// the same modulus for the [`Reducer`] interface
+    modulo_uint: UnsignedLongInt,
+    /// `-modulo^-1 mod 2^64` and `2^128 mod modulo`, only for odd moduli
+    montgomery: Option<(u64, u64)>,
+}
+
+impl SmallModuloContext {
+    /// panics if `modulo` is zero
+    pub fn new(modulo: u64) -> Self {
+        assert!(modulo != 0, "modulo must not be zero");
+
+        let montgomery = if modulo & 1 == 1 {
+            let mut inv = 1u64;
+            for _ in 0..6 {
+                inv = inv.wrapping_mul(2u64.wrapping_sub(modulo.wrapping_mul(inv)));
+            }
+            let r1 = ((1u128 << 64) % modulo as u128) as u64;
+            Some((inv.wrapping_neg(), mul_mod_word(r1, r1, modulo)))
+        } else {
+            None
+        };
+
+        Self { modulo, modulo_uint: UnsignedLongInt::from(modulo), montgomery }
+    }
+
+    pub fn get_modulo(&self) -> u64 {
+        self.modulo
+    }
+
+    pub fn modulo(&self, a: u64) -> SmallModuloUint<'_> {
+        self.wrap(a % self.modulo)
+    }
+
+    pub fn zero(&self) -> SmallModuloUint<'_> {
+        self.wrap(0)
+    }
+
+    pub fn one(&self) -> SmallModuloUint<'_> {
+        self.modulo(1)
+    }
+
+    fn wrap(&self, val: u64) -> SmallModuloUint<'_> {
+        SmallModuloUint { val, context: self }
+    }
+
+    pub fn add(&self, a: &SmallModuloUint, b: &SmallModuloUint) -> SmallModuloUint<'_> {
+        self.wrap(self.add_words(a.val, b.val))
+    }
+
+    pub fn sub(&self, a: &SmallModuloUint, b: &SmallModuloUint) -> SmallModuloUint<'_> {
+        self.wrap(self.sub_words(a.val, b.val))
+    }
+
+    pub fn mul(&self, a: &SmallModuloUint, b: &SmallModuloUint) -> SmallModuloUint<'_> {
+        self.wrap(mul_mod_word(a.val, b.val, self.modulo))
+    }
+
+    /// returns `-a`
+    pub fn neg(&self, a: &SmallModuloUint) -> SmallModuloUint<'_> {
+        self.wrap(self.sub_words(0, a.val))
+    }
+
+    /// returns `a^-1` if `a` is invertible
+    pub fn inv(&self, a: &SmallModuloUint) -> Option<SmallModuloUint<'_>> {
+        self.inv_word(a.val).map(|val| self.wrap(val))
+    }
+
+    /// returns `a * b^-1`, or `None` if `b` is not invertible
+    pub fn div(&self, a: &SmallModuloUint, b: &SmallModuloUint) -> Option<SmallModuloUint<'_>> {
+        self.inv(b).map(|binv| self.mul(a, &binv))
+    }
+
+    /// returns `a^b`
+    pub fn pow<T: BitOps>(&self, a: &SmallModuloUint, b: &T) -> SmallModuloUint<'_> {
+        self.wrap(self.pow_word(a.val, b))
+    }
+
+    /// returns `a^b`
+    pub fn pow_u64(&self, a: &SmallModuloUint, b: u64) -> SmallModuloUint<'_> {
+        self.pow(a, &UnsignedLongInt::from(b))
+    }
+
+    // arithmetic on plain words, also used by `ModuloContext` for one-digit moduli
+
+    pub(crate) fn add_words(&self, a: u64, b: u64) -> u64 {
+        ((a as u128 + b as u128) % self.modulo as u128) as u64
+    }
+
+    pub(crate) fn sub_words(&self, a: u64, b: u64) -> u64 {
+        if a >= b {
+            a - b
+        } else {
+            self.modulo - (b - a)
+        }
+    }
+
+    pub(crate) fn inv_word(&self, a: u64) -> Option<u64> {
+        if self.modulo == 1 {
+            return Some(0);
+        }
+
+        inv_mod_word(a, self.modulo)
+    }
+
+    /// returns `t * 2^-64 mod modulo` for `t < modulo * 2^64`
+    fn redc(&self, t: u128, m_inv: u64) -> u64 {
+        let m = self.modulo;
+        let u = (t as u64).wrapping_mul(m_inv);
+        let (sum, carry) = t.overflowing_add(u as u128 * m as u128);
+        let hi = (sum >> 64) as u64;
+
+        // the true result is below 2m, but may not fit into a word before the subtraction
+        if carry || hi >= m {
+            hi.wrapping_sub(m)
+        } else {
+            hi
+        }
+    }
+
+    /// left-to-right square-and-multiply, in Montgomery form for odd moduli
+    pub(crate) fn pow_word<T: BitOps>(&self, a: u64, e: &T) -> u64 {
+        let m = self.modulo;
+        let highest_bit = match e.get_highest_set_bit() {
+            Some(b) => b,
+            None => return 1 % m,
+        };
+
+        match self.montgomery {
+            Some((m_inv, r2)) => {
+                let a = self.redc(a as u128 * r2 as u128, m_inv);
+                let mut c = a;
+                for i in (0..highest_bit).rev() {
+                    c = self.redc(c as u128 * c as u128, m_inv);
+                    if e.get_bit(i) {
+                        c = self.redc(c as u128 * a as u128, m_inv);
+                    }
+                }
+                self.redc(c as u128, m_inv)
+            }
+            None => {
+                let mut c = a;
+                for i in (0..highest_bit).rev() {
+                    c = mul_mod_word(c, c, m);
+                    if e.get_bit(i) {
+                        c = mul_mod_word(c, a, m);
+                    }
+                }
+                c
+            }
+        }
+    }
+}
+
+impl Reducer for SmallModuloContext {
+    fn modulus(&self) -> &UnsignedLongInt {
+        &self.modulo_uint
+    }
+
+    fn reduce(&self, x: &UnsignedLongInt) -> UnsignedLongInt {
+        let digits = significant_digits(x);
+        if digits.len() > 2 {
+            return UnsignedLongInt::from(rem_word(x, self.modulo));
+        }
+
+        let digit = |i: usize| digits.get(i).copied().unwrap_or(0) as u128;
+        let low = digit(0) | digit(1) << 64;
+        UnsignedLongInt::from((low % self.modulo as u128) as u64)
+    }
+
+    fn as_word(&self) -> Option<&SmallModuloContext> {
+        Some(self)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'c> Add<&SmallModuloUint<'c>> for &SmallModuloUint<'c>
+{
+    type Output = SmallModuloUint<'c>;
+    fn add(self, rhs: &SmallModuloUint<'c>) -> Self::Output {
+        self.context.add(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'c> Sub<&SmallModuloUint<'c>> for &SmallModuloUint<'c>
+{
+    type Output = SmallModuloUint<'c>;
+    fn sub(self, rhs: &SmallModuloUint<'c>) -> Self::Output {
+        self.context.sub(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'c> Mul<&SmallModuloUint<'c>> for &SmallModuloUint<'c>
+{
+    type Output = SmallModuloUint<'c>;
+    fn mul(self, rhs: &SmallModuloUint<'c>) -> Self::Output {
+        self.context.mul(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'c> Div<&SmallModuloUint<'c>> for &SmallModuloUint<'c>
+{
+    type Output = SmallModuloUint<'c>;
+    /// panics if `rhs` is not invertible
+    fn div(self, rhs: &SmallModuloUint<'c>) -> Self::Output {
+        self.context.div(self, rhs).expect("divisor must be invertible")
+    }
+}
+
+impl<'c> Neg for SmallModuloUint<'c>
+{
+    type Output = SmallModuloUint<'c>;
+    fn neg(self) -> Self::Output {
+        self.context.neg(&self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ModuloContext;
+    use crate::reduction::AutoReducer;
+    use crate::BarretParameters;
+
+    #[test]
+    fn matches_generic_context() {
+        for m in [2u64, 1000, 1000003, (1 << 31) - 1, 0xffffffff00000001, u64::MAX, u64::MAX - 1] {
+            let sc = SmallModuloContext::new(m);
+            let m_uint = UnsignedLongInt::from(m);
+            let bc = ModuloContext::with_reducer(&m_uint, AutoReducer::Barret(BarretParameters::new(&m_uint, 1)));
+
+            let (a, b) = (0x9e3779b97f4a7c15u64, 0x3eac59a64ad86a1eu64);
+            let (sa, sb) = (sc.modulo(a), sc.modulo(b));
+            let (ba, bb) = (bc.from_u64(a), bc.from_u64(b));
+            let e = UnsignedLongInt::from(0xdeadbeefcafe);
+
+            assert_eq!(&UnsignedLongInt::from(sa + sb), bc.add(&ba, &bb).value());
+            assert_eq!(&UnsignedLongInt::from(sa - sb), bc.sub(&ba, &bb).value());
+            assert_eq!(&UnsignedLongInt::from(sb - sa), bc.sub(&bb, &ba).value());
+            assert_eq!(&UnsignedLongInt::from(sa * sb), bc.mul(&ba, &bb).value());
+            assert_eq!(&UnsignedLongInt::from(sa.pow(&e)), bc.pow(&ba, &e).value());
+            assert_eq!(sc.inv(&sa).map(UnsignedLongInt::from).as_ref(), bc.inv(&ba).as_ref().map(|x| x.value()));
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        let sc = SmallModuloContext::new(1);
+        assert_eq!(sc.modulo(5).pow_u64(0).value(), 0);
+        assert_eq!(sc.inv(&sc.modulo(5)).map(|x| x.value()), Some(0));
+
+        let sc = SmallModuloContext::new(1000003);
+        let a = sc.modulo(12345);
+        assert_eq!(a.pow_u64(0), sc.one());
+        assert_eq!(a.pow_u64(1000002), sc.one());
+        assert_eq!(a / a, sc.one());
+        assert_eq!(-a + a, sc.zero());
+        assert!(sc.inv(&sc.zero()).is_none());
+
+        // inputs of 2^128 and more are reduced over all digits
+        let x = UnsignedLongInt::from([5, 7, 11].as_slice());
+        assert_eq!(Reducer::reduce(&sc, &x), UnsignedLongInt::div(&x, &UnsignedLongInt::from(1000003)).1);
+    }
+
+    #[test]
+    fn auto_dispatch() {
+        let m = UnsignedLongInt::from((1 << 31) - 1);
+        let mc = ModuloContext::new(&m);
+        assert!(matches!(mc.reducer(), AutoReducer::Word(_)));
+
+        let a = mc.from_u64(0x9e3779b97f4a7c15);
+        assert!(a.pow(&(&m - &UnsignedLongInt::from(1))).is_one());
+        assert_eq!(mc.mul(&a, &mc.inv(&a).expect("modulo is prime")), mc.one());
+    }
+}
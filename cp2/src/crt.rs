@@ -0,0 +1,288 @@
+use std::ops::*;
+use vl_big_ints::UnsignedLongInt;
+use crate::{extended_gcd, mod_inverse};
+use crate::auxiliary::BitOps;
+use crate::context::{ModuloContext, ModuloUint};
+
+/// solves the system `x = r_i (mod m_i)` for arbitrary (not necessarily coprime) moduli,
+/// returns `(x, lcm(m_1, ..., m_n))` with `x < lcm`, or `None` if the system is inconsistent or a modulus is zero
+pub fn crt(congruences: &[(UnsignedLongInt, UnsignedLongInt)]) -> Option<(UnsignedLongInt, UnsignedLongInt)> {
+    let zero = UnsignedLongInt::from(0);
+    let mut x = UnsignedLongInt::from(0);
+    let mut l = UnsignedLongInt::from(1);
+
+    for (r, m) in congruences {
+        if m == &zero {
+            return None;
+        }
+
+        // x + l * t = r (mod m)  <=>  (l / g) * t = (r - x) / g (mod m / g)
+        let (g, _, _) = extended_gcd(&l, m);
+        let r = UnsignedLongInt::div(r, m).1;
+        let x_mod_m = UnsignedLongInt::div(&x, m).1;
+        let diff = match r.checked_sub(&x_mod_m) {
+            Some(diff) => diff,
+            None => m - (&x_mod_m - &r),
+        };
+
+        let (diff, rem) = UnsignedLongInt::div(&diff, &g);
+        if rem != zero {
+            return None;
+        }
+
+        let m_g = m / &g;
+        let l_g = &l / &g;
+        let t = UnsignedLongInt::div(&(diff * mod_inverse(&l_g, &m_g)?), &m_g).1;
+
+        x = x + &l * t;
+        l = l * m_g;
+    }
+
+    Some((x, l))
+}
+
+/// value represented by its residues modulo the factors of a [`CrtContext`]
+#[derive(Clone)]
+pub struct CrtUint<'c> {
+    residues: Vec<ModuloUint<'c>>,
+    context: &'c CrtContext,
+}
+
+impl<'c> CrtUint<'c> {
+    pub fn context(&self) -> &'c CrtContext {
+        self.context
+    }
+
+    /// residues modulo every factor, in the order the factors were given
+    pub fn residues(&self) -> &[ModuloUint<'c>] {
+        &self.residues
+    }
+
+    /// returns `self^e`, every channel is exponentiated separately
+    pub fn pow<T: BitOps>(&self, e: &T) -> CrtUint<'c> {
+        self.context.pow(self, e)
+    }
+}
+
+impl From<CrtUint<'_>> for UnsignedLongInt {
+    /// reconstructs the value with Garner's algorithm
+    fn from(value: CrtUint<'_>) -> Self {
+        value.context.reconstruct(&value)
+    }
+}
+
+/// a constant precomputed modulo the factor of `c`, which needs no further reduction
+fn reduced<'c>(c: &'c ModuloContext<'c>, x: &UnsignedLongInt) -> ModuloUint<'c> {
+    c.from_canonical(x).expect("constants are reduced when the context is built")
+}
+
+/// context for arithmetic modulo `N = m_1 * ... * m_n` with pairwise coprime factors,
+/// numbers are kept as residues modulo every factor and combined back with Garner's algorithm
+pub struct CrtContext {
+    contexts: Vec<ModuloContext<'static>>,
+    modulo: UnsignedLongInt,
+    /// `prefix[i] = m_0 * ... * m_(i - 1)`
+    prefix: Vec<UnsignedLongInt>,
+    /// `garner[i] = prefix[i]^-1 mod m_i`
+    garner: Vec<UnsignedLongInt>,
+    /// `radix[i][j] = m_j mod m_i` for `j < i`
+    radix: Vec<Vec<UnsignedLongInt>>,
+}
+
+impl CrtContext {
+    /// returns `None` if `factors` is empty or the factors are not pairwise coprime
+    pub fn new(factors: &[UnsignedLongInt]) -> Option<Self> {
+        if factors.is_empty() {
+            return None;
+        }
+
+        let mut prefix = Vec::with_capacity(factors.len());
+        let mut garner = Vec::with_capacity(factors.len());
+        let mut radix = Vec::with_capacity(factors.len());
+        let mut modulo = UnsignedLongInt::from(1);
+        for (i, m) in factors.iter().enumerate() {
+            garner.push(mod_inverse(&modulo, m)?);
+            radix.push(factors[..i].iter().map(|m_j| UnsignedLongInt::div(m_j, m).1).collect());
+            prefix.push(modulo.clone());
+            modulo = modulo * m;
+        }
+
+        Some(Self {
+            contexts: factors.iter().map(|m| ModuloContext::new_owned(m.clone())).collect(),
+            modulo,
+            prefix,
+            garner,
+            radix,
+        })
+    }
+
+    /// the product of all factors
+    pub fn get_modulo(&self) -> &UnsignedLongInt {
+        &self.modulo
+    }
+
+    pub fn factors(&self) -> impl Iterator<Item = &UnsignedLongInt> {
+        self.contexts.iter().map(|c| c.get_modulo())
+    }
+
+    /// splits `a mod N` into residues
+    pub fn modulo(&self, a: &UnsignedLongInt) -> CrtUint<'_> {
+        CrtUint {
+            residues: self.contexts.iter().map(|c| c.modulo(a)).collect(),
+            context: self,
+        }
+    }
+
+    /// builds a number from residues modulo every factor, `None` if their count or range is wrong
+    pub fn from_residues(&self, residues: &[UnsignedLongInt]) -> Option<CrtUint<'_>> {
+        if residues.len() != self.contexts.len() {
+            return None;
+        }
+
+        Some(CrtUint {
+            residues: self.contexts.iter().zip(residues).map(|(c, r)| c.from_canonical(r)).collect::<Option<_>>()?,
+            context: self,
+        })
+    }
+
+    /// Garner's mixed-radix reconstruction: `x = v_0 + v_1 * m_0 + v_2 * m_0 * m_1 + ...`
+    pub fn reconstruct(&self, a: &CrtUint) -> UnsignedLongInt {
+        let mut digits: Vec<UnsignedLongInt> = Vec::with_capacity(self.contexts.len());
+        for (i, c) in self.contexts.iter().enumerate() {
+            // the value of the known mixed-radix digits modulo m_i, by Horner's rule
+            let mut u = c.zero();
+            for j in (0..i).rev() {
+                u = c.add(&c.mul(&u, &reduced(c, &self.radix[i][j])), &c.modulo(&digits[j]));
+            }
+
+            let v = c.mul(&c.sub(&a.residues[i], &u), &reduced(c, &self.garner[i]));
+            digits.push(v.into());
+        }
+
+        digits.iter().zip(&self.prefix).fold(UnsignedLongInt::from(0), |x, (v, p)| x + v * p)
+    }
+
+    fn channelwise<'c, F>(&'c self, a: &CrtUint<'c>, b: &CrtUint<'c>, f: F) -> CrtUint<'c>
+    where
+        F: Fn(&'c ModuloContext<'c>, &ModuloUint<'c>, &ModuloUint<'c>) -> ModuloUint<'c>,
+    {
+        CrtUint {
+            residues: self.contexts.iter().zip(a.residues.iter().zip(&b.residues)).map(|(c, (x, y))| f(c, x, y)).collect(),
+            context: self,
+        }
+    }
+
+    pub fn add<'c>(&'c self, a: &CrtUint<'c>, b: &CrtUint<'c>) -> CrtUint<'c> {
+        self.channelwise(a, b, |c, x, y| c.add(x, y))
+    }
+
+    pub fn sub<'c>(&'c self, a: &CrtUint<'c>, b: &CrtUint<'c>) -> CrtUint<'c> {
+        self.channelwise(a, b, |c, x, y| c.sub(x, y))
+    }
+
+    pub fn mul<'c>(&'c self, a: &CrtUint<'c>, b: &CrtUint<'c>) -> CrtUint<'c> {
+        self.channelwise(a, b, |c, x, y| c.mul(x, y))
+    }
+
+    /// returns `a^e`, every channel works with numbers the size of its factor only
+    pub fn pow<'c, T: BitOps>(&'c self, a: &CrtUint<'c>, e: &T) -> CrtUint<'c> {
+        CrtUint {
+            residues: self.contexts.iter().zip(&a.residues).map(|(c, x)| c.pow(x, e)).collect(),
+            context: self,
+        }
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'c> Add<&CrtUint<'c>> for &CrtUint<'c>
+{
+    type Output = CrtUint<'c>;
+    fn add(self, rhs: &CrtUint<'c>) -> Self::Output {
+        self.context.add(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'c> Sub<&CrtUint<'c>> for &CrtUint<'c>
+{
+    type Output = CrtUint<'c>;
+    fn sub(self, rhs: &CrtUint<'c>) -> Self::Output {
+        self.context.sub(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'c> Mul<&CrtUint<'c>> for &CrtUint<'c>
+{
+    type Output = CrtUint<'c>;
+    fn mul(self, rhs: &CrtUint<'c>) -> Self::Output {
+        self.context.mul(self, rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    fn congruences(pairs: &[(u64, u64)]) -> Vec<(UnsignedLongInt, UnsignedLongInt)> {
+        pairs.iter().map(|&(r, m)| (UnsignedLongInt::from(r), UnsignedLongInt::from(m))).collect()
+    }
+
+    #[test]
+    fn crt_coprime() {
+        let (x, l) = crt(&congruences(&[(2, 3), (3, 5), (2, 7)])).expect("moduli are coprime");
+        assert_eq!((x, l), (UnsignedLongInt::from(23), UnsignedLongInt::from(105)));
+        assert_eq!(crt(&[]), Some((UnsignedLongInt::from(0), UnsignedLongInt::from(1))));
+    }
+
+    #[test]
+    fn crt_non_coprime() {
+        let (x, l) = crt(&congruences(&[(3, 4), (5, 6), (11, 15)])).expect("system is consistent");
+        assert_eq!((x, l), (UnsignedLongInt::from(11), UnsignedLongInt::from(60)));
+
+        assert!(crt(&congruences(&[(1, 4), (2, 6)])).is_none());
+        assert!(crt(&congruences(&[(1, 4), (2, 0)])).is_none());
+    }
+
+    #[test]
+    fn crt_context() -> Result<(), Box<dyn Error>> {
+        let p = UnsignedLongInt::from_str("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff")?;
+        let q = UnsignedLongInt::from_str("7fffffffffffffffffffffffffffffff")?;
+        let n = &p * &q;
+        let a = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784ee7a0b2df68cd23a1e5e01fe37626dc77d4cd7e8d1da5365ca90ed72529f3952f")?;
+        let b = UnsignedLongInt::from_str("3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f135")?;
+
+        let cc = CrtContext::new(&[p.clone(), q.clone()]).expect("factors are coprime");
+        let mc = ModuloContext::new(&n);
+        assert_eq!(cc.get_modulo(), &n);
+
+        let (ac, bc) = (cc.modulo(&a), cc.modulo(&b));
+        let (am, bm) = (mc.modulo(&a), mc.modulo(&b));
+        assert_eq!(&UnsignedLongInt::from(ac.clone()), am.value());
+        assert_eq!(&UnsignedLongInt::from(&ac + &bc), mc.add(&am, &bm).value());
+        assert_eq!(&UnsignedLongInt::from(&bc - &ac), mc.sub(&bm, &am).value());
+        assert_eq!(&UnsignedLongInt::from(&ac * &bc), mc.mul(&am, &bm).value());
+        assert_eq!(&UnsignedLongInt::from(ac.pow(&b)), mc.pow(&am, &b).value());
+
+        let x = cc.from_residues(&[UnsignedLongInt::from(2), UnsignedLongInt::from(3)]).expect("residues are reduced");
+        let (expected, _) = crt(&[(UnsignedLongInt::from(2), p.clone()), (UnsignedLongInt::from(3), q.clone())]).expect("coprime");
+        assert_eq!(UnsignedLongInt::from(x), expected);
+        assert!(cc.from_residues(&[p.clone(), UnsignedLongInt::from(3)]).is_none());
+
+        assert!(CrtContext::new(&[UnsignedLongInt::from(6), UnsignedLongInt::from(9)]).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn garner_many_factors() {
+        let factors: Vec<_> = [3u64, 5, 7, 11, 13, 1000003].iter().map(|&m| UnsignedLongInt::from(m)).collect();
+        let cc = CrtContext::new(&factors).expect("factors are coprime");
+
+        for x in [0u64, 1, 12345, 15015 * 1000003 - 1] {
+            assert_eq!(UnsignedLongInt::from(cc.modulo(&UnsignedLongInt::from(x))), UnsignedLongInt::from(x));
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::{bit_length, significant_digits};
+use crate::context::{ModuloContext, ModuloUint};
+use crate::crt::crt;
+use crate::factor::factorize;
+
+/// largest baby-step table built by [`bsgs`], bounds its memory to a few megabytes
+pub const BSGS_MAX_TABLE: u64 = 1 << 16;
+
+/// number of precomputed multipliers of the r-adding walk in [`pollard_rho_log`]
+const RHO_MULTIPLIERS: usize = 16;
+
+/// walks that meet with equal exponents tolerated before [`pollard_rho_log`] gives up,
+/// that only happens when the target is not a power of the base
+const RHO_MAX_USELESS: usize = 64;
+
+/// returns `x < order` with `g^x = h`, or `None` if there is no such `x`
+///
+/// `order` must be a multiple of the order of `g`, it is factored and the logarithm is
+/// assembled from its residues modulo every prime power with Pohlig-Hellman, the prime order
+/// subproblems are solved with [`bsgs`] when their table fits into [`BSGS_MAX_TABLE`]
+/// and with [`pollard_rho_log`] otherwise
+pub fn discrete_log<'m>(ctx: &'m ModuloContext<'m>, g: &ModuloUint<'m>, h: &ModuloUint<'m>, order: &UnsignedLongInt) -> Option<UnsignedLongInt> {
+    pohlig_hellman(ctx, g, h, order, &factorize(order))
+}
+
+/// [`discrete_log`] with a precomputed factorization of `order`, as returned by [`factorize`]
+pub fn pohlig_hellman<'m>(
+    ctx: &'m ModuloContext<'m>,
+    g: &ModuloUint<'m>,
+    h: &ModuloUint<'m>,
+    order: &UnsignedLongInt,
+    factors: &[(UnsignedLongInt, u32)],
+) -> Option<UnsignedLongInt> {
+    ctx.check_context(&[g, h]).ok()?;
+
+    let mut congruences = Vec::with_capacity(factors.len());
+    for (p, e) in factors {
+        let pe = p.pow(&UnsignedLongInt::from(*e as u64));
+        let cofactor = order / &pe;
+        let gi = g.pow(&cofactor);
+        let hi = h.pow(&cofactor);
+
+        // gi has order p^f for some f <= e
+        let mut f = 0;
+        let mut t = gi.clone();
+        while !t.is_one() {
+            if f == *e {
+                return None;
+            }
+            t = t.pow(p);
+            f += 1;
+        }
+        if f == 0 {
+            continue;
+        }
+
+        // x = d_0 + d_1 p + ... + d_{f-1} p^{f-1}, every digit is a logarithm base gamma of order p
+        let gamma = gi.pow(&p.pow(&UnsignedLongInt::from(f as u64 - 1)));
+        let gi_inv = ctx.inv(&gi)?;
+        let mut x = UnsignedLongInt::from(0);
+        let mut pk = UnsignedLongInt::from(1);
+        for k in 0..f {
+            let hk = ctx.mul(&gi_inv.pow(&x), &hi).pow(&p.pow(&UnsignedLongInt::from((f - 1 - k) as u64)));
+            let d = prime_order_log(ctx, &gamma, &hk, p)?;
+            x = x + &d * &pk;
+            pk = &pk * p;
+        }
+
+        congruences.push((x, pk));
+    }
+
+    let (x, _) = crt(&congruences)?;
+    if &g.pow(&x) == h {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+/// logarithm in a subgroup of prime order `p`
+fn prime_order_log<'m>(ctx: &'m ModuloContext<'m>, g: &ModuloUint<'m>, h: &ModuloUint<'m>, p: &UnsignedLongInt) -> Option<UnsignedLongInt> {
+    if !h.pow(p).is_one() {
+        return None;
+    }
+
+    if bit_length(p) <= 2 * BSGS_MAX_TABLE.trailing_zeros() as usize {
+        bsgs(ctx, g, h, p)
+    } else {
+        let walkers = thread::available_parallelism().map_or(1, |n| n.get());
+        pollard_rho_log(ctx, g, h, p, walkers)
+    }
+}
+
+fn to_u64(x: &UnsignedLongInt) -> Option<u64> {
+    match significant_digits(x) {
+        [] => Some(0),
+        [d] => Some(*d),
+        _ => None,
+    }
+}
+
+fn key(x: &ModuloUint) -> Vec<u64> {
+    significant_digits(x.value()).to_vec()
+}
+
+/// baby-step giant-step: returns the smallest `x < order` with `g^x = h`, or `None` if there is no such `x`
+///
+/// The table of baby steps holds `min(sqrt(order), BSGS_MAX_TABLE)` entries, larger orders
+/// take proportionally more giant steps instead of more memory, about `order / BSGS_MAX_TABLE`
+/// for orders above `BSGS_MAX_TABLE^2`.
+pub fn bsgs<'m>(ctx: &'m ModuloContext<'m>, g: &ModuloUint<'m>, h: &ModuloUint<'m>, order: &UnsignedLongInt) -> Option<UnsignedLongInt> {
+    ctx.check_context(&[g, h]).ok()?;
+
+    let m = match to_u64(order) {
+        Some(n) => ((n as f64).sqrt().ceil() as u64).clamp(1, BSGS_MAX_TABLE),
+        None => BSGS_MAX_TABLE,
+    };
+
+    let mut table = HashMap::with_capacity(m as usize);
+    let mut e = ctx.one();
+    for j in 0..m {
+        table.entry(key(&e)).or_insert(j);
+        e = ctx.mul(&e, g);
+    }
+
+    // e = g^m
+    let giant = ctx.inv(&e)?;
+    let m_uint = UnsignedLongInt::from(m);
+    let steps = (order + &UnsignedLongInt::from(m - 1)) / &m_uint;
+    let one = UnsignedLongInt::from(1);
+
+    let mut gamma = h.clone();
+    let mut i = UnsignedLongInt::from(0);
+    while i < steps {
+        if let Some(&j) = table.get(&key(&gamma)) {
+            let x = &i * &m_uint + UnsignedLongInt::from(j);
+            return if &x < order { Some(x) } else { None };
+        }
+        gamma = ctx.mul(&gamma, &giant);
+        i = i + &one;
+    }
+
+    None
+}
+
+/// SplitMix64, seeds the walks deterministically
+fn splitmix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+fn random_below<'n>(nc: &'n ModuloContext<'n>, state: &mut u64) -> ModuloUint<'n> {
+    let digits = [splitmix(state), splitmix(state), splitmix(state)];
+    nc.modulo(&UnsignedLongInt::from(digits.as_slice()))
+}
+
+struct RhoState {
+    /// distinguished points and the exponents `(a, b)` with `point = g^a h^b`
+    points: HashMap<Vec<u64>, (UnsignedLongInt, UnsignedLongInt)>,
+    useless: usize,
+    result: Option<UnsignedLongInt>,
+}
+
+/// Pollard's rho with distinguished points: returns `x < order` with `g^x = h` for `g` of prime order `order`,
+/// or `None` if `h` is not a power of `g`
+///
+/// Every one of the `walkers` threads runs r-adding walks `X = g^a h^b` from random starting points
+/// until it reaches a distinguished point, which goes into a shared table. Two walks that reach the
+/// same point with different exponents give `a1 + x b1 = a2 + x b2 (mod order)`.
+pub fn pollard_rho_log<'m>(
+    ctx: &'m ModuloContext<'m>,
+    g: &ModuloUint<'m>,
+    h: &ModuloUint<'m>,
+    order: &UnsignedLongInt,
+    walkers: usize,
+) -> Option<UnsignedLongInt> {
+    ctx.check_context(&[g, h]).ok()?;
+
+    let nc = ModuloContext::new(order);
+    let mut seed = 0x2545f4914f6cdd1d;
+    let multipliers: Vec<_> = (0..RHO_MULTIPLIERS).map(|_| {
+        let a = random_below(&nc, &mut seed);
+        let b = random_below(&nc, &mut seed);
+        (ctx.mul(&g.pow(&a), &h.pow(&b)), a, b)
+    }).collect();
+
+    // about order^(1/4) steps between distinguished points, walks that loop are restarted
+    let distinguished_bits = (bit_length(order) / 4).min(30);
+    let distinguished_mask = (1u64 << distinguished_bits) - 1;
+    let max_walk = 16usize << distinguished_bits;
+
+    let done = AtomicBool::new(false);
+    let state = Mutex::new(RhoState { points: HashMap::new(), useless: 0, result: None });
+
+    thread::scope(|scope| {
+        for walker in 0..walkers.max(1) {
+            let (nc, multipliers, done, state) = (&nc, &multipliers, &done, &state);
+            scope.spawn(move || {
+                let mut seed = splitmix(&mut (walker as u64 + 1)) ^ seed;
+                while !done.load(Ordering::Relaxed) {
+                    let mut a = random_below(nc, &mut seed);
+                    let mut b = random_below(nc, &mut seed);
+                    let mut x = ctx.mul(&g.pow(&a), &h.pow(&b));
+
+                    for _ in 0..max_walk {
+                        let mixed = x.value().digits().first().copied().unwrap_or(0).wrapping_mul(0x9e3779b97f4a7c15);
+                        if (mixed >> 30) & distinguished_mask == 0 {
+                            record(nc, state, done, (key(&x), a, b), g, h);
+                            break;
+                        }
+
+                        let (m, ma, mb) = &multipliers[(mixed >> 60) as usize % RHO_MULTIPLIERS];
+                        x = ctx.mul(&x, m);
+                        a = nc.add(&a, ma);
+                        b = nc.add(&b, mb);
+                    }
+                }
+            });
+        }
+    });
+
+    state.into_inner().expect("walker must not panic").result
+}
+
+/// stores a distinguished point, or solves for the logarithm if another walk already reached it
+fn record<'n, 'm>(
+    nc: &'n ModuloContext<'n>,
+    state: &Mutex<RhoState>,
+    done: &AtomicBool,
+    (point, a, b): (Vec<u64>, ModuloUint<'n>, ModuloUint<'n>),
+    g: &ModuloUint<'m>,
+    h: &ModuloUint<'m>,
+) {
+    let mut state = state.lock().expect("walker must not panic");
+    let (a2, b2) = match state.points.get(&point) {
+        Some((a2, b2)) => (nc.modulo(a2), nc.modulo(b2)),
+        None => {
+            state.points.insert(point, (a.into(), b.into()));
+            return;
+        }
+    };
+
+    // g^a h^b = g^a2 h^b2  =>  x = (a - a2) / (b2 - b)
+    let x = nc.div(&nc.sub(&a, &a2), &nc.sub(&b2, &b)).map(UnsignedLongInt::from);
+    match x {
+        Some(x) if &g.pow(&x) == h => {
+            state.result = Some(x);
+            done.store(true, Ordering::Relaxed);
+        }
+        _ => {
+            state.useless += 1;
+            if state.useless > RHO_MAX_USELESS {
+                done.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    #[test]
+    fn baby_step_giant_step() {
+        let p = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&p);
+        let g = mc.from_u64(2);
+        let order = UnsignedLongInt::from(1000002);
+
+        let h = g.pow_u64(123456);
+        assert_eq!(bsgs(&mc, &g, &h, &order), Some(UnsignedLongInt::from(123456)));
+        assert_eq!(bsgs(&mc, &g, &mc.one(), &order), Some(UnsignedLongInt::from(0)));
+        assert_eq!(discrete_log(&mc, &g, &h, &order), Some(UnsignedLongInt::from(123456)));
+    }
+
+    #[test]
+    fn smooth_order() -> Result<(), Box<dyn Error>> {
+        // p - 1 = 2^32 * 3 * 5 * 17 * 257 * 65537, 7 is a primitive root
+        let p = UnsignedLongInt::from_str("ffffffff00000001")?;
+        let order = UnsignedLongInt::from_str("ffffffff00000000")?;
+        let mc = ModuloContext::new(&p);
+        let g = mc.from_u64(7);
+
+        for x in [0u64, 1, 0xfffffffe00000000, 0x9e3779b97f4a7c15] {
+            let h = g.pow_u64(x);
+            let expected = UnsignedLongInt::div(&UnsignedLongInt::from(x), &order).1;
+            assert_eq!(discrete_log(&mc, &g, &h, &order), Some(expected));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn prime_order_rho() -> Result<(), Box<dyn Error>> {
+        // p - 1 = 2 * 3 * 7 * q, g generates the subgroup of order q
+        let p = UnsignedLongInt::from_str("a80000041b")?;
+        let q = UnsignedLongInt::from_str("400000019")?;
+        let mc = ModuloContext::new(&p);
+        let g = mc.from_u64(0x4e567fa7d7);
+
+        let x = UnsignedLongInt::from(0x123456789);
+        let h = g.pow(&x);
+        assert_eq!(pollard_rho_log(&mc, &g, &h, &q, 2), Some(x.clone()));
+        assert_eq!(pollard_rho_log(&mc, &g, &h, &q, 1), Some(x.clone()));
+        assert_eq!(discrete_log(&mc, &g, &h, &q), Some(x));
+
+        Ok(())
+    }
+
+    #[test]
+    fn no_logarithm() {
+        // 4 only generates the quadratic residues, 2 is not one of them modulo 1000003
+        let p = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&p);
+        let g = mc.from_u64(4);
+        let h = mc.from_u64(2);
+
+        assert_eq!(discrete_log(&mc, &g, &h, &UnsignedLongInt::from(1000002)), None);
+        assert_eq!(bsgs(&mc, &g, &h, &UnsignedLongInt::from(500001)), None);
+    }
+
+    #[test]
+    fn bsgs_capped_table() -> Result<(), Box<dyn Error>> {
+        // the order is far above BSGS_MAX_TABLE^2, the logarithm is found after about 2^17 giant steps
+        let p = UnsignedLongInt::from_str("ffffffff00000001")?;
+        let order = UnsignedLongInt::from_str("ffffffff00000000")?;
+        let mc = ModuloContext::new(&p);
+        let g = mc.from_u64(7);
+
+        let x = UnsignedLongInt::from((1u64 << 33) + 12345);
+        assert_eq!(bsgs(&mc, &g, &g.pow(&x), &order), Some(x));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,379 @@
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::BitOps;
+use crate::reduction::Reducer;
+
+/// widest window accepted by the windowed methods, their tables hold up to `2^MAX_WINDOW_WIDTH` powers
+pub const MAX_WINDOW_WIDTH: usize = 16;
+
+/// panics unless `1 <= k <= MAX_WINDOW_WIDTH`
+fn check_window_width(k: usize) {
+    assert!((1..=MAX_WINDOW_WIDTH).contains(&k), "window width must be between 1 and {MAX_WINDOW_WIDTH}");
+}
+
+/// window width for k-ary and sliding-window exponentiation, chosen from the exponent bit length
+pub fn window_width(bits: usize) -> usize {
+    match bits {
+        b if b > 671 => 6,
+        b if b > 239 => 5,
+        b if b > 79 => 4,
+        b if b > 23 => 3,
+        _ => 1,
+    }
+}
+
+/// returns `width` bits of `e` starting with bit `from` (exponent bits above the highest set one are zeroes)
+pub(crate) fn get_window<T: BitOps>(e: &T, from: usize, width: usize, highest_bit: usize) -> usize {
+    let mut window = 0;
+    for i in (from..(from + width).min(highest_bit + 1)).rev() {
+        window = (window << 1) | e.get_bit(i) as usize;
+    }
+
+    window
+}
+
+/// `1 mod m`, the result of raising to the zero exponent (zero for `m = 1`)
+fn one<R: Reducer>(r: &R) -> UnsignedLongInt {
+    r.reduce(&UnsignedLongInt::from(1))
+}
+
+/// plain right-to-left square-and-multiply
+pub fn pow_right_to_left<R: Reducer, T: BitOps>(r: &R, a: &UnsignedLongInt, e: &T) -> UnsignedLongInt {
+    let highest_bit = match e.get_highest_set_bit() {
+        Some(b) => b,
+        None => return one(r),
+    };
+
+    let mut c = UnsignedLongInt::from(1);
+    let mut a = a.to_owned();
+
+    for i in 0..(highest_bit + 1) {
+        if e.get_bit(i) {
+            c = r.reduce(&(&c * &a));
+        }
+        a = r.reduce(&(&a * &a));
+    }
+
+    c
+}
+
+/// left-to-right square-and-multiply, multiplies by the same (unsquared) base every time
+pub fn pow_left_to_right<R: Reducer, T: BitOps>(r: &R, a: &UnsignedLongInt, e: &T) -> UnsignedLongInt {
+    let mut c = one(r);
+
+    if let Some(highest_bit) = e.get_highest_set_bit() {
+        for i in (0..(highest_bit + 1)).rev() {
+            c = r.reduce(&(&c * &c));
+            if e.get_bit(i) {
+                c = r.reduce(&(&c * a));
+            }
+        }
+    }
+
+    c
+}
+
+/// fixed-window (k-ary) exponentiation with precomputed `a^0, ..., a^(2^k - 1)`
+///
+/// panics unless `1 <= k <= MAX_WINDOW_WIDTH`
+pub fn pow_k_ary<R: Reducer, T: BitOps>(r: &R, a: &UnsignedLongInt, e: &T, k: usize) -> UnsignedLongInt {
+    check_window_width(k);
+    let highest_bit = match e.get_highest_set_bit() {
+        Some(b) => b,
+        None => return one(r),
+    };
+
+    let mut table = Vec::with_capacity(1 << k);
+    table.push(UnsignedLongInt::from(1));
+    for i in 1..(1 << k) {
+        table.push(r.reduce(&(&table[i - 1] * a)));
+    }
+
+    let windows = highest_bit / k + 1;
+    let mut c = table[get_window(e, (windows - 1) * k, k, highest_bit)].clone();
+    for w in (0..(windows - 1)).rev() {
+        for _ in 0..k {
+            c = r.reduce(&(&c * &c));
+        }
+
+        let window = get_window(e, w * k, k, highest_bit);
+        if window != 0 {
+            c = r.reduce(&(&c * &table[window]));
+        }
+    }
+
+    c
+}
+
+/// left-to-right sliding-window exponentiation with precomputed odd powers `a, a^3, ..., a^(2^k - 1)`
+///
+/// panics unless `1 <= k <= MAX_WINDOW_WIDTH`
+pub fn pow_sliding_window<R: Reducer, T: BitOps>(r: &R, a: &UnsignedLongInt, e: &T, k: usize) -> UnsignedLongInt {
+    check_window_width(k);
+    let highest_bit = match e.get_highest_set_bit() {
+        Some(b) => b,
+        None => return one(r),
+    };
+
+    let a_squared = r.reduce(&(a * a));
+    let mut odd_powers = Vec::with_capacity(1 << (k - 1));
+    odd_powers.push(a.to_owned());
+    for i in 1..(1 << (k - 1)) {
+        odd_powers.push(r.reduce(&(&odd_powers[i - 1] * &a_squared)));
+    }
+
+    let mut c = UnsignedLongInt::from(1);
+    let mut i = highest_bit as isize;
+    while i >= 0 {
+        if !e.get_bit(i as usize) {
+            c = r.reduce(&(&c * &c));
+            i -= 1;
+            continue;
+        }
+
+        // the longest window of at most k bits that ends with a set bit
+        let mut l = (i - k as isize + 1).max(0);
+        while !e.get_bit(l as usize) {
+            l += 1;
+        }
+
+        let width = (i - l + 1) as usize;
+        for _ in 0..width {
+            c = r.reduce(&(&c * &c));
+        }
+        let window = get_window(e, l as usize, width, highest_bit);
+        c = r.reduce(&(&c * &odd_powers[window >> 1]));
+
+        i = l - 1;
+    }
+
+    c
+}
+
+/// multiplies `acc` by `x`, treating `None` as 1 to skip multiplications by one
+fn mul_or_init<R: Reducer>(r: &R, acc: Option<UnsignedLongInt>, x: &UnsignedLongInt) -> UnsignedLongInt {
+    match acc {
+        Some(acc) => r.reduce(&(&acc * x)),
+        None => x.clone(),
+    }
+}
+
+/// the window of `e` starting with bit `from`, or 0 if `e` has no set bits that high
+fn get_term_window<T: BitOps>(e: &T, from: usize, width: usize, highest_bit: Option<usize>) -> usize {
+    match highest_bit {
+        Some(highest_bit) if from <= highest_bit => get_window(e, from, width, highest_bit),
+        _ => 0,
+    }
+}
+
+/// interleaved fixed-window multi-exponentiation `a_1^e_1 * ... * a_n^e_n` (Straus),
+/// all terms share the squarings, `k = 1` is Shamir's trick
+///
+/// panics unless `1 <= k <= MAX_WINDOW_WIDTH`
+pub fn multi_pow_straus<R: Reducer, T: BitOps>(r: &R, terms: &[(&UnsignedLongInt, &T)], k: usize) -> UnsignedLongInt {
+    check_window_width(k);
+    let highest_bits: Vec<Option<usize>> = terms.iter().map(|(_, e)| e.get_highest_set_bit()).collect();
+    let highest_bit = match highest_bits.iter().flatten().max() {
+        Some(&b) => b,
+        None => return one(r),
+    };
+
+    let tables: Vec<Vec<UnsignedLongInt>> = terms
+        .iter()
+        .map(|(a, _)| {
+            let mut table = Vec::with_capacity(1 << k);
+            table.push(one(r));
+            for i in 1..(1 << k) {
+                table.push(r.reduce(&(&table[i - 1] * *a)));
+            }
+            table
+        })
+        .collect();
+
+    let mut c: Option<UnsignedLongInt> = None;
+    for w in (0..(highest_bit / k + 1)).rev() {
+        if let Some(acc) = c.as_mut() {
+            for _ in 0..k {
+                *acc = r.reduce(&(&*acc * &*acc));
+            }
+        }
+
+        for (((_, e), table), &term_highest_bit) in terms.iter().zip(&tables).zip(&highest_bits) {
+            let window = get_term_window(*e, w * k, k, term_highest_bit);
+            if window != 0 {
+                c = Some(mul_or_init(r, c, &table[window]));
+            }
+        }
+    }
+
+    c.unwrap_or_else(|| one(r))
+}
+
+/// Pippenger's bucket method for `a_1^e_1 * ... * a_n^e_n` with `c`-bit windows:
+/// every window costs about `n + 2^(c + 1)` multiplications no matter how many terms there are
+///
+/// panics unless `1 <= c <= MAX_WINDOW_WIDTH`
+pub fn multi_pow_pippenger<R: Reducer, T: BitOps>(r: &R, terms: &[(&UnsignedLongInt, &T)], c: usize) -> UnsignedLongInt {
+    check_window_width(c);
+    let highest_bits: Vec<Option<usize>> = terms.iter().map(|(_, e)| e.get_highest_set_bit()).collect();
+    let highest_bit = match highest_bits.iter().flatten().max() {
+        Some(&b) => b,
+        None => return one(r),
+    };
+
+    let mut result: Option<UnsignedLongInt> = None;
+    for w in (0..(highest_bit / c + 1)).rev() {
+        if let Some(acc) = result.as_mut() {
+            for _ in 0..c {
+                *acc = r.reduce(&(&*acc * &*acc));
+            }
+        }
+
+        // bucket d collects the bases whose current window equals d
+        let mut buckets: Vec<Option<UnsignedLongInt>> = vec![None; 1 << c];
+        for ((a, e), &term_highest_bit) in terms.iter().zip(&highest_bits) {
+            let d = get_term_window(*e, w * c, c, term_highest_bit);
+            if d != 0 {
+                buckets[d] = Some(mul_or_init(r, buckets[d].take(), a));
+            }
+        }
+
+        // prod_d bucket_d^d as a product of suffix products
+        let mut suffix: Option<UnsignedLongInt> = None;
+        let mut window_product: Option<UnsignedLongInt> = None;
+        for bucket in buckets.iter().skip(1).rev() {
+            if let Some(bucket) = bucket {
+                suffix = Some(mul_or_init(r, suffix, bucket));
+            }
+            if let Some(suffix) = &suffix {
+                window_product = Some(mul_or_init(r, window_product, suffix));
+            }
+        }
+
+        if let Some(window_product) = window_product {
+            result = Some(mul_or_init(r, result, &window_product));
+        }
+    }
+
+    result.unwrap_or_else(|| one(r))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BarretParameters;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    #[test]
+    fn methods_agree() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str("3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f135")?;
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+        let r = BarretParameters::new(&m, m.num_digits());
+
+        for e in ["1", "2", "10", "ffff", "8000000000000001", "ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca677"] {
+            let e = UnsignedLongInt::from_str(e)?;
+            let expected = pow_right_to_left(&r, &a, &e);
+
+            assert_eq!(pow_left_to_right(&r, &a, &e), expected);
+            for k in 1..7 {
+                assert_eq!(pow_k_ary(&r, &a, &e, k), expected);
+                assert_eq!(pow_sliding_window(&r, &a, &e, k), expected);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn zero_exponent() {
+        let m = UnsignedLongInt::from(1000003);
+        let r = BarretParameters::new(&m, m.num_digits());
+        let a = UnsignedLongInt::from(12345);
+        let e = UnsignedLongInt::from(0);
+
+        assert_eq!(pow_right_to_left(&r, &a, &e), UnsignedLongInt::from(1));
+        assert_eq!(pow_left_to_right(&r, &a, &e), UnsignedLongInt::from(1));
+        assert_eq!(pow_k_ary(&r, &a, &e, 4), UnsignedLongInt::from(1));
+        assert_eq!(pow_sliding_window(&r, &a, &e, 4), UnsignedLongInt::from(1));
+    }
+
+    #[test]
+    fn widest_window() {
+        let m = UnsignedLongInt::from(1000003);
+        let r = BarretParameters::new(&m, m.num_digits());
+        let a = UnsignedLongInt::from(12345);
+        let e = UnsignedLongInt::from(0xdeadbeefu64);
+
+        let expected = pow_right_to_left(&r, &a, &e);
+        assert_eq!(pow_k_ary(&r, &a, &e, MAX_WINDOW_WIDTH), expected);
+        assert_eq!(pow_sliding_window(&r, &a, &e, MAX_WINDOW_WIDTH), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "window width")]
+    fn k_ary_empty_window() {
+        let m = UnsignedLongInt::from(1000003);
+        let r = BarretParameters::new(&m, m.num_digits());
+        pow_k_ary(&r, &UnsignedLongInt::from(2), &UnsignedLongInt::from(5), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "window width")]
+    fn sliding_window_too_wide() {
+        let m = UnsignedLongInt::from(1000003);
+        let r = BarretParameters::new(&m, m.num_digits());
+        pow_sliding_window(&r, &UnsignedLongInt::from(2), &UnsignedLongInt::from(5), MAX_WINDOW_WIDTH + 1);
+    }
+
+    #[test]
+    fn multi_pow_methods_agree() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+        let r = BarretParameters::new(&m, m.num_digits());
+
+        let bases: Vec<UnsignedLongInt> = (1..10u64).map(|i| r.reduce(&UnsignedLongInt::from(i.wrapping_mul(0x9e3779b97f4a7c15)))).collect();
+        let exponents: Vec<UnsignedLongInt> = ["0", "1", "ffff", "8000000000000001", "ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca677", "3", "0", "abcdef", "1234567890abcdef1234567890"]
+            .iter()
+            .map(|e| UnsignedLongInt::from_str(e))
+            .collect::<Result<_, _>>()?;
+        let terms: Vec<(&UnsignedLongInt, &UnsignedLongInt)> = bases.iter().zip(&exponents).collect();
+
+        let mut expected = UnsignedLongInt::from(1);
+        for (a, e) in &terms {
+            expected = r.reduce(&(&expected * &pow_left_to_right(&r, a, *e)));
+        }
+
+        for k in 1..6 {
+            assert_eq!(multi_pow_straus(&r, &terms, k), expected);
+            assert_eq!(multi_pow_pippenger(&r, &terms, k), expected);
+        }
+
+        let zero = UnsignedLongInt::from(0);
+        assert_eq!(multi_pow_straus(&r, &[(&bases[0], &zero)], 3), UnsignedLongInt::from(1));
+        assert_eq!(multi_pow_pippenger::<_, UnsignedLongInt>(&r, &[], 3), UnsignedLongInt::from(1));
+
+        let e = UnsignedLongInt::from(0xdeadbeefu64);
+        let expected = pow_left_to_right(&r, &bases[0], &e);
+        assert_eq!(multi_pow_straus(&r, &[(&bases[0], &e)], MAX_WINDOW_WIDTH), expected);
+        assert_eq!(multi_pow_pippenger(&r, &[(&bases[0], &e)], MAX_WINDOW_WIDTH), expected);
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "window width")]
+    fn straus_empty_window() {
+        let m = UnsignedLongInt::from(1000003);
+        let r = BarretParameters::new(&m, m.num_digits());
+        let (a, e) = (UnsignedLongInt::from(2), UnsignedLongInt::from(5));
+        multi_pow_straus(&r, &[(&a, &e)], 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "window width")]
+    fn pippenger_window_too_wide() {
+        let m = UnsignedLongInt::from(1000003);
+        let r = BarretParameters::new(&m, m.num_digits());
+        let (a, e) = (UnsignedLongInt::from(2), UnsignedLongInt::from(5));
+        multi_pow_pippenger(&r, &[(&a, &e)], MAX_WINDOW_WIDTH + 1);
+    }
+}
@@ -0,0 +1,329 @@
+use vl_big_ints::UnsignedLongInt;
+use crate::gcd;
+use crate::auxiliary::{bit_length, is_prime_word, rem_word, significant_digits};
+use crate::context::{ModuloContext, ModuloUint};
+
+/// primes below this bound are found by trial division before running Pollard's rho
+pub const TRIAL_DIVISION_BOUND: u64 = 1 << 12;
+
+/// Miller-Rabin bases, deterministic for numbers below `3.3 * 10^24`
+const MILLER_RABIN_BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// number of steps Brent's variant of Pollard's rho multiplies together before taking a gcd
+const BRENT_BATCH: usize = 128;
+
+/// Baillie-PSW test: Miller-Rabin with the first twelve prime bases, which alone is deterministic
+/// below `3.3 * 10^24`, followed by a strong Lucas test, no composite is known to pass both
+///
+/// Composites passing every fixed Miller-Rabin base can be constructed, the Lucas test is what
+/// rejects them for larger inputs. Word-sized numbers are tested deterministically.
+pub fn is_probable_prime(n: &UnsignedLongInt) -> bool {
+    match significant_digits(n) {
+        [] => return false,
+        [n] => return is_prime_word(*n),
+        _ => {}
+    }
+
+    for p in MILLER_RABIN_BASES {
+        if rem_word(n, p) == 0 {
+            return false;
+        }
+    }
+
+    let one = UnsignedLongInt::from(1);
+    let n_minus_one = n - &one;
+    let s = (0..).find(|&i| n_minus_one.get_bit(i)).expect("n - 1 is not zero");
+    let d = n_minus_one.shr(&UnsignedLongInt::from(s as u64));
+
+    let mc = ModuloContext::new(n);
+    let minus_one = mc.modulo(&n_minus_one);
+    'witness: for a in MILLER_RABIN_BASES {
+        let mut x = mc.from_u64(a).pow(&d);
+        if x.is_one() || x == minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = mc.mul(&x, &x);
+            if x == minus_one {
+                continue 'witness;
+            }
+        }
+
+        return false;
+    }
+
+    is_strong_lucas_prime(&mc)
+}
+
+/// Jacobi symbol `(a / n)` for odd `n`
+fn jacobi_word(mut a: u64, mut n: u64) -> i32 {
+    let mut result = 1;
+    a %= n;
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if n % 8 == 3 || n % 8 == 5 {
+                result = -result;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a % 4 == 3 && n % 4 == 3 {
+            result = -result;
+        }
+        a %= n;
+    }
+
+    if n == 1 { result } else { 0 }
+}
+
+/// Jacobi symbol `(d / n)` for a small odd `|d|` and an odd `n`, by quadratic reciprocity
+fn jacobi(d: i64, n: &UnsignedLongInt) -> i32 {
+    let k = d.unsigned_abs();
+    let n_mod_4 = rem_word(n, 4);
+    let mut result = jacobi_word(rem_word(n, k), k);
+    if k % 4 == 3 && n_mod_4 == 3 {
+        result = -result;
+    }
+    // (-1 / n) = -1 for n = 3 (mod 4)
+    if d < 0 && n_mod_4 == 3 {
+        result = -result;
+    }
+
+    result
+}
+
+fn is_square(n: &UnsignedLongInt) -> bool {
+    // Newton's iteration from above converges to floor(sqrt(n))
+    let mut x = UnsignedLongInt::from(1).shl(bit_length(n).div_ceil(2));
+    loop {
+        let y = (&x + &(n / &x)).shr(&UnsignedLongInt::from(1));
+        if y >= x {
+            break;
+        }
+        x = y;
+    }
+
+    &x * &x == *n
+}
+
+/// strong Lucas probable prime test with Selfridge's parameters `P = 1`, `Q = (1 - D) / 4`
+/// for the first `D` of `5, -7, 9, -11, ...` with `(D / n) = -1`, `n` must be odd and
+/// have no factors up to 37
+fn is_strong_lucas_prime(mc: &ModuloContext) -> bool {
+    let n = mc.get_modulo();
+    let mut d: i64 = 5;
+    loop {
+        match jacobi(d, n) {
+            -1 => break,
+            // |d| < n divides n
+            0 => return false,
+            _ => {}
+        }
+        // no D exists for squares, check once the search takes unusually long
+        if d == 21 && is_square(n) {
+            return false;
+        }
+        d = if d > 0 { -(d + 2) } else { -d + 2 };
+    }
+
+    let signed = |x: i64| if x < 0 { mc.neg(&mc.from_u64(x.unsigned_abs())) } else { mc.from_u64(x as u64) };
+    let q = signed((1 - d) / 4);
+    let d = signed(d);
+    let half = mc.modulo(&(n + &UnsignedLongInt::from(1)).shr(&UnsignedLongInt::from(1)));
+
+    // n + 1 = k * 2^s with odd k
+    let n_plus_one = n + &UnsignedLongInt::from(1);
+    let s = (0..).find(|&i| n_plus_one.get_bit(i)).expect("n + 1 is not zero");
+    let k = n_plus_one.shr(&UnsignedLongInt::from(s as u64));
+
+    // U_k, V_k and Q^k, doubling U_j, V_j and adding one index for every set bit
+    let mut u = mc.one();
+    let mut v = mc.one();
+    let mut qk = q.clone();
+    let highest_bit = k.get_highest_set_bit().expect("k is odd");
+    for i in (0..highest_bit).rev() {
+        u = mc.mul(&u, &v);
+        v = mc.sub(&mc.mul(&v, &v), &mc.add(&qk, &qk));
+        qk = mc.mul(&qk, &qk);
+        if k.get_bit(i) {
+            (u, v) = (mc.mul(&mc.add(&u, &v), &half), mc.mul(&mc.add(&mc.mul(&d, &u), &v), &half));
+            qk = mc.mul(&qk, &q);
+        }
+    }
+
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+    // V_(k 2^r) for r < s
+    for _ in 1..s {
+        v = mc.sub(&mc.mul(&v, &v), &mc.add(&qk, &qk));
+        if v.is_zero() {
+            return true;
+        }
+        qk = mc.mul(&qk, &qk);
+    }
+
+    false
+}
+
+/// returns `gcd(a, n)` for `n > 0`, `a` may be zero
+fn gcd_or_n(a: &UnsignedLongInt, n: &UnsignedLongInt) -> UnsignedLongInt {
+    if significant_digits(a).is_empty() {
+        n.clone()
+    } else {
+        gcd(a, n)
+    }
+}
+
+/// returns a nontrivial factor of an odd composite `n` with Brent's variant of Pollard's rho,
+/// or `None` if every tried polynomial `x^2 + c` fails
+pub fn pollard_rho(n: &UnsignedLongInt) -> Option<UnsignedLongInt> {
+    let is_one = |g: &UnsignedLongInt| bit_length(g) == 1;
+    let is_n = |g: &UnsignedLongInt| significant_digits(g) == significant_digits(n);
+    let mc = ModuloContext::new(n);
+
+    for c in 1..64 {
+        let c = mc.from_u64(c);
+        let f = |x: &ModuloUint| mc.add(&mc.mul(x, x), &c);
+
+        let mut y = mc.from_u64(2);
+        let mut x = y.clone();
+        let mut ys = y.clone();
+        let mut q = mc.one();
+        let mut g = UnsignedLongInt::from(1);
+        let mut r = 1usize;
+
+        while is_one(&g) {
+            x = y.clone();
+            for _ in 0..r {
+                y = f(&y);
+            }
+
+            let mut k = 0;
+            while k < r && is_one(&g) {
+                ys = y.clone();
+                for _ in 0..BRENT_BATCH.min(r - k) {
+                    y = f(&y);
+                    q = mc.mul(&q, &mc.sub(&x, &y));
+                }
+                g = gcd_or_n(q.value(), n);
+                k += BRENT_BATCH;
+            }
+            r *= 2;
+        }
+
+        // the batch overshot, redo it one step at a time
+        if is_n(&g) {
+            g = UnsignedLongInt::from(1);
+            while is_one(&g) {
+                ys = f(&ys);
+                g = gcd_or_n(mc.sub(&x, &ys).value(), n);
+            }
+        }
+
+        if !is_n(&g) {
+            return Some(g);
+        }
+    }
+
+    None
+}
+
+/// factors `n` into primes with trial division, Pollard's rho and Miller-Rabin,
+/// returns `(p, e)` pairs sorted by `p`, `1` has no factors
+///
+/// panics if `n` is zero or Pollard's rho fails to split a composite factor
+pub fn factorize(n: &UnsignedLongInt) -> Vec<(UnsignedLongInt, u32)> {
+    assert!(!significant_digits(n).is_empty(), "zero can not be factored");
+
+    let mut factors = Vec::new();
+    let mut n = n.clone();
+
+    let mut p = 2;
+    while p < TRIAL_DIVISION_BOUND && bit_length(&n) > 1 {
+        let mut e = 0;
+        while rem_word(&n, p) == 0 {
+            n = &n / &UnsignedLongInt::from(p);
+            e += 1;
+        }
+        if e > 0 {
+            factors.push((UnsignedLongInt::from(p), e));
+        }
+        p += if p == 2 { 1 } else { 2 };
+    }
+
+    let mut stack = vec![n];
+    let mut large: Vec<UnsignedLongInt> = Vec::new();
+    while let Some(m) = stack.pop() {
+        if bit_length(&m) <= 1 {
+            continue;
+        }
+        if is_probable_prime(&m) {
+            large.push(m);
+            continue;
+        }
+
+        let d = pollard_rho(&m).expect("Pollard's rho must split a composite number");
+        stack.push(&m / &d);
+        stack.push(d);
+    }
+
+    large.sort();
+    for p in large {
+        match factors.last_mut() {
+            Some((q, e)) if q == &p => *e += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    fn product(factors: &[(UnsignedLongInt, u32)]) -> UnsignedLongInt {
+        factors.iter().fold(UnsignedLongInt::from(1), |acc, (p, e)| acc * p.pow(&UnsignedLongInt::from(*e as u64)))
+    }
+
+    #[test]
+    fn primality() -> Result<(), Box<dyn Error>> {
+        assert!(is_probable_prime(&UnsignedLongInt::from(1000003)));
+        assert!(!is_probable_prime(&UnsignedLongInt::from(1)));
+        assert!(is_probable_prime(&UnsignedLongInt::from_str("7fffffffffffffffffffffffffffffff")?));
+        assert!(is_probable_prime(&UnsignedLongInt::from_str("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff")?));
+        // 2^128 + 1 = 59649589127497217 * 5704689200685129054721
+        assert!(!is_probable_prime(&UnsignedLongInt::from_str("100000000000000000000000000000001")?));
+        // 3317044064679887385961981 = 1287836182261 * 2575672364521 is a strong pseudoprime to all twelve bases
+        assert!(!is_probable_prime(&UnsignedLongInt::from_str("2be6951adc5b22410a5fd")?));
+        // squares have no Lucas parameter D with (D / n) = -1
+        assert!(!is_probable_prime(&(UnsignedLongInt::from(1000003 * 1000003) * UnsignedLongInt::from(1000003 * 1000003))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn factorization() -> Result<(), Box<dyn Error>> {
+        assert!(factorize(&UnsignedLongInt::from(1)).is_empty());
+
+        let n = UnsignedLongInt::from(2 * 2 * 2 * 3 * 1000003 * 1000003);
+        let factors = factorize(&n);
+        assert_eq!(factors, vec![
+            (UnsignedLongInt::from(2), 3),
+            (UnsignedLongInt::from(3), 1),
+            (UnsignedLongInt::from(1000003), 2),
+        ]);
+
+        // 4294967311 * 4294967357 * 1000003
+        let n = UnsignedLongInt::from_str("f42430487abe43689d579")?;
+        let factors = factorize(&n);
+        assert_eq!(factors.len(), 3);
+        assert_eq!(product(&factors), n);
+        assert!(factors.iter().all(|(p, _)| is_probable_prime(p)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,621 @@
+//! Prime fields with the modulus fixed at compile time, see [`crate::define_field!`].
+//!
+//! Elements are stored in Montgomery form in a fixed array of `N` little-endian 64-bit limbs,
+//! all reduction constants are computed by `const fn`s during compilation.
+
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::BitOps;
+
+/// number of 64-bit limbs needed for a hex string
+pub const fn hex_limbs(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() && bytes[i] == b'0' {
+        i += 1;
+    }
+
+    if i == bytes.len() {
+        1
+    } else {
+        (bytes.len() - i).div_ceil(16)
+    }
+}
+
+/// parses a hex string into little-endian limbs, panics (at compile time in const context)
+/// on invalid digits or if the number does not fit
+pub const fn parse_hex<const N: usize>(s: &str) -> [u64; N] {
+    let bytes = s.as_bytes();
+    let mut limbs = [0u64; N];
+    let mut i = bytes.len();
+    let mut nibble = 0;
+    while i > 0 {
+        i -= 1;
+        let d = match bytes[i] {
+            b'0'..=b'9' => bytes[i] - b'0',
+            b'a'..=b'f' => bytes[i] - b'a' + 10,
+            b'A'..=b'F' => bytes[i] - b'A' + 10,
+            _ => panic!("invalid hex digit"),
+        } as u64;
+
+        if d != 0 {
+            assert!(nibble / 16 < N, "number does not fit");
+            limbs[nibble / 16] |= d << (4 * (nibble % 16));
+        }
+        nibble += 1;
+    }
+
+    limbs
+}
+
+/// returns `a >= b`
+pub const fn geq<const N: usize>(a: &[u64; N], b: &[u64; N]) -> bool {
+    let mut i = N;
+    while i > 0 {
+        i -= 1;
+        if a[i] != b[i] {
+            return a[i] > b[i];
+        }
+    }
+
+    true
+}
+
+/// returns `a + b` and the carry out of the top limb
+pub const fn add_limbs<const N: usize>(a: &[u64; N], b: &[u64; N]) -> ([u64; N], bool) {
+    let mut r = [0u64; N];
+    let mut carry = false;
+    let mut i = 0;
+    while i < N {
+        let (s, c1) = a[i].overflowing_add(b[i]);
+        let (s, c2) = s.overflowing_add(carry as u64);
+        r[i] = s;
+        carry = c1 || c2;
+        i += 1;
+    }
+
+    (r, carry)
+}
+
+/// returns `a - b` and the borrow out of the top limb
+pub const fn sub_limbs<const N: usize>(a: &[u64; N], b: &[u64; N]) -> ([u64; N], bool) {
+    let mut r = [0u64; N];
+    let mut borrow = false;
+    let mut i = 0;
+    while i < N {
+        let (d, b1) = a[i].overflowing_sub(b[i]);
+        let (d, b2) = d.overflowing_sub(borrow as u64);
+        r[i] = d;
+        borrow = b1 || b2;
+        i += 1;
+    }
+
+    (r, borrow)
+}
+
+/// returns `a + b mod m` for `a, b < m`
+pub const fn mod_add<const N: usize>(a: &[u64; N], b: &[u64; N], m: &[u64; N]) -> [u64; N] {
+    let (s, carry) = add_limbs(a, b);
+    if carry || geq(&s, m) {
+        sub_limbs(&s, m).0
+    } else {
+        s
+    }
+}
+
+/// returns `a - b mod m` for `a, b < m`
+pub const fn mod_sub<const N: usize>(a: &[u64; N], b: &[u64; N], m: &[u64; N]) -> [u64; N] {
+    let (d, borrow) = sub_limbs(a, b);
+    if borrow {
+        add_limbs(&d, m).0
+    } else {
+        d
+    }
+}
+
+/// returns `2^k mod m` by doubling, `m` must be greater than 1
+pub const fn pow2_mod<const N: usize>(m: &[u64; N], k: usize) -> [u64; N] {
+    let mut r = [0u64; N];
+    r[0] = 1;
+    let mut i = 0;
+    while i < k {
+        r = mod_add(&r, &r, m);
+        i += 1;
+    }
+
+    r
+}
+
+/// calculate `-m0^-1 mod 2^64` with Newton iteration, `m0` must be odd
+pub const fn mont_inv(m0: u64) -> u64 {
+    let mut inv = 1u64;
+    let mut i = 0;
+    while i < 6 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(m0.wrapping_mul(inv)));
+        i += 1;
+    }
+
+    inv.wrapping_neg()
+}
+
+/// returns `a * b * 2^(-64N) mod m` for `a, b < m` (CIOS Montgomery multiplication)
+pub const fn mont_mul<const N: usize>(a: &[u64; N], b: &[u64; N], m: &[u64; N], inv: u64) -> [u64; N] {
+    let mut t = [0u64; N];
+    // the two limbs above `t`
+    let mut t_n = 0u64;
+    let mut t_n1;
+
+    let mut i = 0;
+    while i < N {
+        let mut carry = 0u128;
+        let mut j = 0;
+        while j < N {
+            let s = t[j] as u128 + a[j] as u128 * b[i] as u128 + carry;
+            t[j] = s as u64;
+            carry = s >> 64;
+            j += 1;
+        }
+        let s = t_n as u128 + carry;
+        t_n = s as u64;
+        t_n1 = (s >> 64) as u64;
+
+        // add a multiple of `m` that zeroes the lowest limb, then drop it
+        let u = t[0].wrapping_mul(inv) as u128;
+        let mut carry = (t[0] as u128 + u * m[0] as u128) >> 64;
+        let mut j = 1;
+        while j < N {
+            let s = t[j] as u128 + u * m[j] as u128 + carry;
+            t[j - 1] = s as u64;
+            carry = s >> 64;
+            j += 1;
+        }
+        let s = t_n as u128 + carry;
+        t[N - 1] = s as u64;
+        t_n = t_n1 + (s >> 64) as u64;
+
+        i += 1;
+    }
+
+    if t_n != 0 || geq(&t, m) {
+        sub_limbs(&t, m).0
+    } else {
+        t
+    }
+}
+
+/// values `2, 3, ...` tried by [`PrimeField::sqrt`] before it gives up finding a quadratic non-residue
+pub const NON_RESIDUE_CANDIDATES: u64 = 128;
+
+/// compile-time checks of the modulus used by [`crate::define_field!`]
+pub const fn check_modulus<const N: usize>(m: &[u64; N]) {
+    assert!(m[0] & 1 == 1, "field modulus must be an odd prime");
+    assert!(N > 1 || m[0] > 1, "field modulus must be an odd prime");
+}
+
+/// prime field with the modulus known at compile time, implemented by types from [`crate::define_field!`]
+///
+/// The modulus must be an odd prime, otherwise [`PrimeField::inv`] returns garbage and
+/// [`PrimeField::sqrt`] may return `None` for squares.
+pub trait PrimeField<const N: usize>: Sized + Copy + Eq {
+    const MODULUS: [u64; N];
+    /// `-MODULUS^-1 mod 2^64`
+    const INV: u64 = mont_inv(Self::MODULUS[0]);
+    /// `2^(64N) mod MODULUS`, i.e. 1 in Montgomery form
+    const R1: [u64; N] = pow2_mod(&Self::MODULUS, 64 * N);
+    /// `2^(128N) mod MODULUS`, used for conversion into Montgomery form
+    const R2: [u64; N] = pow2_mod(&Self::MODULUS, 128 * N);
+
+    /// wraps limbs that are already in Montgomery form
+    fn from_montgomery(limbs: [u64; N]) -> Self;
+
+    /// limbs in Montgomery form
+    fn montgomery(&self) -> &[u64; N];
+
+    fn zero() -> Self {
+        Self::from_montgomery([0; N])
+    }
+
+    fn one() -> Self {
+        Self::from_montgomery(Self::R1)
+    }
+
+    fn modulus() -> UnsignedLongInt {
+        UnsignedLongInt::from(Self::MODULUS.as_slice())
+    }
+
+    /// returns `a mod MODULUS`
+    fn from_uint(a: &UnsignedLongInt) -> Self {
+        let a = if a.num_digits() <= N {
+            a.clone()
+        } else {
+            UnsignedLongInt::div(a, &Self::modulus()).1
+        };
+
+        let mut limbs = [0u64; N];
+        limbs[..a.num_digits()].copy_from_slice(a.digits());
+        if geq(&limbs, &Self::MODULUS) {
+            let a = UnsignedLongInt::div(&a, &Self::modulus()).1;
+            limbs = [0u64; N];
+            limbs[..a.num_digits()].copy_from_slice(a.digits());
+        }
+
+        Self::from_montgomery(mont_mul(&limbs, &Self::R2, &Self::MODULUS, Self::INV))
+    }
+
+    /// canonical value in `[0, MODULUS)`
+    fn to_uint(&self) -> UnsignedLongInt {
+        let mut one = [0u64; N];
+        one[0] = 1;
+        let limbs = mont_mul(self.montgomery(), &one, &Self::MODULUS, Self::INV);
+        UnsignedLongInt::from(limbs.as_slice())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.montgomery().iter().all(|&d| d == 0)
+    }
+
+    fn square(&self) -> Self {
+        mul(self, self)
+    }
+
+    /// returns `self^e` (left-to-right square-and-multiply)
+    fn pow<T: BitOps>(&self, e: &T) -> Self {
+        let mut c = Self::one();
+        if let Some(highest_bit) = e.get_highest_set_bit() {
+            for i in (0..(highest_bit + 1)).rev() {
+                c = c.square();
+                if e.get_bit(i) {
+                    c = mul(&c, self);
+                }
+            }
+        }
+
+        c
+    }
+
+    /// returns `self^-1` by Fermat's little theorem, `None` for zero
+    fn inv(&self) -> Option<Self> {
+        if self.is_zero() {
+            return None;
+        }
+
+        let mut two = [0u64; N];
+        two[0] = 2;
+        let e = sub_limbs(&Self::MODULUS, &two).0;
+        Some(self.pow(&UnsignedLongInt::from(e.as_slice())))
+    }
+
+    /// returns a square root of `self` (Tonelli-Shanks), `None` if `self` is not a quadratic residue
+    ///
+    /// The search for a non-residue gives up after [`NON_RESIDUE_CANDIDATES`] values and returns `None`,
+    /// for a prime modulus one of them is a non-residue in practice.
+    fn sqrt(&self) -> Option<Self> {
+        if self.is_zero() {
+            return Some(*self);
+        }
+
+        let p_minus_1 = Self::modulus() - UnsignedLongInt::from(1);
+        let half = p_minus_1.shr(&UnsignedLongInt::from(1));
+
+        // Euler's criterion
+        if self.pow(&half) != Self::one() {
+            return None;
+        }
+
+        // p - 1 = q * 2^s with odd q
+        let mut s = 0;
+        while !p_minus_1.get_bit(s) {
+            s += 1;
+        }
+        let q = p_minus_1.shr(&UnsignedLongInt::from(s as u64));
+
+        // any quadratic non-residue z, i.e. z^((p - 1) / 2) = -1
+        let minus_one = neg(&Self::one());
+        let z = (0..NON_RESIDUE_CANDIDATES)
+            .scan(Self::one(), |z, _| {
+                *z = add(z, &Self::one());
+                Some(*z)
+            })
+            .find(|z| z.pow(&half) == minus_one)?;
+
+        let mut m = s;
+        let mut c = z.pow(&q);
+        let mut t = self.pow(&q);
+        let mut r = self.pow(&(&q + &UnsignedLongInt::from(1)).shr(&UnsignedLongInt::from(1)));
+
+        while t != Self::one() {
+            // the least i with t^(2^i) = 1
+            let mut i = 0;
+            let mut t2i = t;
+            while t2i != Self::one() {
+                t2i = t2i.square();
+                i += 1;
+                if i == m {
+                    return None;
+                }
+            }
+
+            let mut b = c;
+            for _ in 0..(m - i - 1) {
+                b = b.square();
+            }
+            m = i;
+            c = b.square();
+            t = mul(&t, &c);
+            r = mul(&r, &b);
+        }
+
+        if r.square() == *self {
+            Some(r)
+        } else {
+            None
+        }
+    }
+}
+
+/// returns `a + b`, used by the operators of [`crate::define_field!`] types
+pub fn add<F: PrimeField<N>, const N: usize>(a: &F, b: &F) -> F {
+    F::from_montgomery(mod_add(a.montgomery(), b.montgomery(), &F::MODULUS))
+}
+
+/// returns `a - b`
+pub fn sub<F: PrimeField<N>, const N: usize>(a: &F, b: &F) -> F {
+    F::from_montgomery(mod_sub(a.montgomery(), b.montgomery(), &F::MODULUS))
+}
+
+/// returns `a * b`
+pub fn mul<F: PrimeField<N>, const N: usize>(a: &F, b: &F) -> F {
+    F::from_montgomery(mont_mul(a.montgomery(), b.montgomery(), &F::MODULUS, F::INV))
+}
+
+/// returns `-a`
+pub fn neg<F: PrimeField<N>, const N: usize>(a: &F) -> F {
+    F::from_montgomery(mod_sub(&[0; N], a.montgomery(), &F::MODULUS))
+}
+
+/// declares a `Copy` prime field element type for an odd prime modulus given as a hex string
+///
+/// ```
+/// use vl_big_ints_modulo::define_field;
+/// use vl_big_ints_modulo::field::PrimeField;
+///
+/// define_field!(pub P256, "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+///
+/// let a = P256::from(5);
+/// assert_eq!(a * a.inv().unwrap(), P256::one());
+/// ```
+#[macro_export]
+macro_rules! define_field {
+    ($vis:vis $name:ident, $hex:literal) => {
+        #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+        $vis struct $name([u64; $crate::field::hex_limbs($hex)]);
+
+        impl $crate::field::PrimeField<{ $crate::field::hex_limbs($hex) }> for $name {
+            const MODULUS: [u64; $crate::field::hex_limbs($hex)] = $crate::field::parse_hex($hex);
+
+            fn from_montgomery(limbs: [u64; $crate::field::hex_limbs($hex)]) -> Self {
+                Self(limbs)
+            }
+
+            fn montgomery(&self) -> &[u64; $crate::field::hex_limbs($hex)] {
+                &self.0
+            }
+        }
+
+        const _: () = $crate::field::check_modulus(&<$name as $crate::field::PrimeField<{ $crate::field::hex_limbs($hex) }>>::MODULUS);
+
+        impl ::std::default::Default for $name {
+            fn default() -> Self {
+                <Self as $crate::field::PrimeField<{ $crate::field::hex_limbs($hex) }>>::zero()
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                write!(f, "{}", $crate::field::PrimeField::to_uint(self))
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                write!(f, "{}({})", stringify!($name), $crate::field::PrimeField::to_uint(self))
+            }
+        }
+
+        impl ::std::convert::From<&$crate::UnsignedLongInt> for $name {
+            fn from(value: &$crate::UnsignedLongInt) -> Self {
+                $crate::field::PrimeField::from_uint(value)
+            }
+        }
+
+        impl ::std::convert::From<u64> for $name {
+            fn from(value: u64) -> Self {
+                $crate::field::PrimeField::from_uint(&$crate::UnsignedLongInt::from(value))
+            }
+        }
+
+        impl ::std::convert::From<$name> for $crate::UnsignedLongInt {
+            fn from(value: $name) -> Self {
+                $crate::field::PrimeField::to_uint(&value)
+            }
+        }
+
+        $crate::define_field!(@binary $name, Add, add, AddAssign, add_assign, |a, b| $crate::field::add(a, b));
+        $crate::define_field!(@binary $name, Sub, sub, SubAssign, sub_assign, |a, b| $crate::field::sub(a, b));
+        $crate::define_field!(@binary $name, Mul, mul, MulAssign, mul_assign, |a, b| $crate::field::mul(a, b));
+        $crate::define_field!(@binary $name, Div, div, DivAssign, div_assign, |a, b| $crate::field::mul(
+            a,
+            &$crate::field::PrimeField::inv(b).expect("divisor must be invertible"),
+        ));
+
+        impl ::std::ops::Neg for $name {
+            type Output = $name;
+            fn neg(self) -> $name {
+                $crate::field::neg(&self)
+            }
+        }
+
+        impl ::std::ops::Neg for &$name {
+            type Output = $name;
+            fn neg(self) -> $name {
+                $crate::field::neg(self)
+            }
+        }
+
+        impl ::std::iter::Sum for $name {
+            fn sum<I: Iterator<Item = $name>>(iter: I) -> $name {
+                iter.fold(<$name as ::std::default::Default>::default(), |a, b| a + b)
+            }
+        }
+
+        impl ::std::iter::Product for $name {
+            fn product<I: Iterator<Item = $name>>(iter: I) -> $name {
+                iter.fold($crate::field::PrimeField::one(), |a, b| a * b)
+            }
+        }
+    };
+
+    (@binary $name:ident, $op:ident, $method:ident, $op_assign:ident, $method_assign:ident, |$a:ident, $b:ident| $body:expr) => {
+        impl ::std::ops::$op<&$name> for &$name {
+            type Output = $name;
+            fn $method(self, rhs: &$name) -> $name {
+                let ($a, $b) = (self, rhs);
+                $body
+            }
+        }
+
+        impl ::std::ops::$op<$name> for $name {
+            type Output = $name;
+            fn $method(self, rhs: $name) -> $name {
+                ::std::ops::$op::$method(&self, &rhs)
+            }
+        }
+
+        impl ::std::ops::$op<&$name> for $name {
+            type Output = $name;
+            fn $method(self, rhs: &$name) -> $name {
+                ::std::ops::$op::$method(&self, rhs)
+            }
+        }
+
+        impl ::std::ops::$op<$name> for &$name {
+            type Output = $name;
+            fn $method(self, rhs: $name) -> $name {
+                ::std::ops::$op::$method(self, &rhs)
+            }
+        }
+
+        impl ::std::ops::$op_assign<$name> for $name {
+            fn $method_assign(&mut self, rhs: $name) {
+                *self = ::std::ops::$op::$method(&*self, &rhs);
+            }
+        }
+
+        impl ::std::ops::$op_assign<&$name> for $name {
+            fn $method_assign(&mut self, rhs: &$name) {
+                *self = ::std::ops::$op::$method(&*self, rhs);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ModuloContext;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    const M: &str = "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff";
+
+    crate::define_field!(P256, "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+    // p = 1 (mod 2^32), exercises the general Tonelli-Shanks path
+    crate::define_field!(Small, "ffffffff00000001");
+
+    #[test]
+    fn constants() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str(M)?;
+        assert_eq!(P256::modulus(), m);
+        assert_eq!(hex_limbs(M), 4);
+        assert_eq!(hex_limbs("0001"), 1);
+        assert_eq!(UnsignedLongInt::from(P256::one()), UnsignedLongInt::from(1));
+        assert_eq!(std::mem::size_of::<P256>(), 32);
+
+        Ok(())
+    }
+
+    #[test]
+    fn matches_context() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784ee7a0b2df68cd23a1e5e01fe37626dc77d4cd7e8d1da5365ca90ed72529f3952f")?;
+        let b = UnsignedLongInt::from_str("3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f135")?;
+        let m = UnsignedLongInt::from_str(M)?;
+        let mc = ModuloContext::new(&m);
+        let (amod, bmod) = (mc.modulo(&a), mc.modulo(&b));
+        let (fa, fb) = (P256::from(&a), P256::from(&b));
+
+        assert_eq!(UnsignedLongInt::from(fa + fb), mc.add(&amod, &bmod).into());
+        assert_eq!(UnsignedLongInt::from(fa - fb), mc.sub(&amod, &bmod).into());
+        assert_eq!(UnsignedLongInt::from(fb - fa), mc.sub(&bmod, &amod).into());
+        assert_eq!(UnsignedLongInt::from(fa * fb), mc.mul(&amod, &bmod).into());
+        assert_eq!(UnsignedLongInt::from(fa.pow(&b)), mc.pow(&amod, &b).into());
+        assert_eq!(fa / fb * fb, fa);
+        assert_eq!(-fa + fa, P256::zero());
+
+        let mut c = fa;
+        c *= fb;
+        c += fa;
+        assert_eq!(c, fa * fb + fa);
+
+        Ok(())
+    }
+
+    #[test]
+    fn inversion() {
+        assert!(P256::zero().inv().is_none());
+        for v in [1, 2, 3, 0xdeadbeef] {
+            let a = Small::from(v);
+            assert_eq!(a * a.inv().expect("non-zero"), Small::one());
+        }
+    }
+
+    #[test]
+    fn square_root() {
+        for field_value in [0u64, 1, 4, 5, 12345, 0x7fff0000] {
+            let a = P256::from(field_value);
+            let root = a.square().sqrt().expect("square must have a root");
+            assert_eq!(root.square(), a.square());
+
+            let a = Small::from(field_value);
+            let root = a.square().sqrt().expect("square must have a root");
+            assert_eq!(root.square(), a.square());
+        }
+
+        // -1 is not a square when p = 3 (mod 4)
+        assert!((-P256::one()).sqrt().is_none());
+        let non_residues = (2..50).map(Small::from).filter(|a| a.sqrt().is_none()).count();
+        assert!(non_residues > 0);
+    }
+
+    #[test]
+    fn composite_square_root() {
+        // no z has z^4 = -1 modulo 9, the search for a non-residue must give up
+        crate::define_field!(Nine, "9");
+        for v in 1..9 {
+            if let Some(root) = Nine::from(v).sqrt() {
+                assert_eq!(root.square(), Nine::from(v));
+            }
+        }
+
+        // (2^64 - 2^32 + 1) * (2^61 - 1), Euler's criterion holds for 1 but the search is capped
+        crate::define_field!(Semiprime, "1fffffffdfffffff20000000ffffffff");
+        if let Some(root) = Semiprime::one().sqrt() {
+            assert_eq!(root.square(), Semiprime::one());
+        }
+    }
+
+    #[test]
+    fn iterators() {
+        let values: Vec<Small> = (1..=5).map(Small::from).collect();
+        assert_eq!(values.iter().copied().sum::<Small>(), Small::from(15));
+        assert_eq!(values.into_iter().product::<Small>(), Small::from(120));
+    }
+}
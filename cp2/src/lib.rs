@@ -2,19 +2,34 @@ pub use vl_big_ints::{UnsignedLongInt};
 mod auxiliary;
 use auxiliary::*;
 
+pub mod cache;
 pub mod context;
+pub mod crt;
+pub mod dlog;
+pub mod exponentiation;
+pub mod factor;
+pub mod field;
+pub mod linear;
+pub mod matrix;
+pub mod order;
+pub mod poly;
+pub mod reduction;
+pub mod rns;
+pub mod signed;
+pub use signed::SignedLongInt;
 
-struct BarretParameters<'m> {
-    modulo: &'m UnsignedLongInt,
+/// precomputed parameters for Barrett reduction of `2k`-digit operands
+pub struct BarretParameters {
+    modulo: UnsignedLongInt,
     k: usize,
     mu: UnsignedLongInt,
 }
 
-impl<'m> BarretParameters<'m> {
-    pub fn new(modulo: &'m UnsignedLongInt, k: usize) -> Self {
+impl BarretParameters {
+    pub fn new(modulo: &UnsignedLongInt, k: usize) -> Self {
         let mu = Self::calculate_mu(k as u64, &modulo);
         Self {
-            modulo,
+            modulo: modulo.clone(),
             k,
             mu,
         }
@@ -76,9 +91,121 @@ pub fn lcm(a: &UnsignedLongInt, b: &UnsignedLongInt) -> UnsignedLongInt {
     (a * b) / gcd(a, b)
 }
 
-fn barret_reduction<'m>(x: &UnsignedLongInt, m: &'m BarretParameters) -> UnsignedLongInt {
+/// returns `(g, x, y)` such that `g = gcd(a, b) = a * x + b * y`
+pub fn extended_gcd(a: &UnsignedLongInt, b: &UnsignedLongInt) -> (UnsignedLongInt, SignedLongInt, SignedLongInt) {
+    let const_0 = UnsignedLongInt::from(0);
+
+    let (mut old_r, mut r) = (a.to_owned(), b.to_owned());
+    let (mut old_x, mut x) = (SignedLongInt::from(1), SignedLongInt::from(0));
+    let (mut old_y, mut y) = (SignedLongInt::from(0), SignedLongInt::from(1));
+
+    while r != const_0 {
+        let (q, rem) = UnsignedLongInt::div(&old_r, &r);
+        let q = SignedLongInt::from(q);
+
+        old_r = std::mem::replace(&mut r, rem);
+        let next_x = &old_x - &q * &x;
+        old_x = std::mem::replace(&mut x, next_x);
+        let next_y = &old_y - &q * &y;
+        old_y = std::mem::replace(&mut y, next_y);
+    }
+
+    (old_r, old_x, old_y)
+}
+
+/// same as [`extended_gcd`], but uses only shifts and subtractions (binary extended gcd)
+pub fn binary_extended_gcd(a: &UnsignedLongInt, b: &UnsignedLongInt) -> (UnsignedLongInt, SignedLongInt, SignedLongInt) {
+    let const_0 = UnsignedLongInt::from(0);
+    let const_1 = UnsignedLongInt::from(1);
+
+    if a == &const_0 {
+        return (b.to_owned(), SignedLongInt::from(0), SignedLongInt::from(1));
+    }
+    if b == &const_0 {
+        return (a.to_owned(), SignedLongInt::from(1), SignedLongInt::from(0));
+    }
+
+    let mut x = a.to_owned();
+    let mut y = b.to_owned();
+    let mut shift = 0;
+    while x.is_even() && y.is_even() {
+        x = x.shr(&const_1);
+        y = y.shr(&const_1);
+        shift += 1;
+    }
+
+    let sx = SignedLongInt::from(&x);
+    let sy = SignedLongInt::from(&y);
+    let mut u = x.clone();
+    let mut v = y.clone();
+    let (mut ca, mut cb) = (SignedLongInt::from(1), SignedLongInt::from(0));
+    let (mut cc, mut cd) = (SignedLongInt::from(0), SignedLongInt::from(1));
+
+    // invariants: ca * x + cb * y = u, cc * x + cd * y = v
+    loop {
+        while u.is_even() {
+            u = u.shr(&const_1);
+            if ca.is_even() && cb.is_even() {
+                ca = ca.half();
+                cb = cb.half();
+            } else {
+                ca = (&ca + &sy).half();
+                cb = (&cb - &sx).half();
+            }
+        }
+
+        while v.is_even() {
+            v = v.shr(&const_1);
+            if cc.is_even() && cd.is_even() {
+                cc = cc.half();
+                cd = cd.half();
+            } else {
+                cc = (&cc + &sy).half();
+                cd = (&cd - &sx).half();
+            }
+        }
+
+        if u >= v {
+            u = &u - &v;
+            ca = &ca - &cc;
+            cb = &cb - &cd;
+        } else {
+            v = &v - &u;
+            cc = &cc - &ca;
+            cd = &cd - &cb;
+        }
+
+        if u == const_0 {
+            return (v.shl(shift), cc, cd);
+        }
+    }
+}
+
+/// returns `a^-1 mod m` if it exists
+pub fn mod_inverse(a: &UnsignedLongInt, m: &UnsignedLongInt) -> Option<UnsignedLongInt> {
+    if m == &UnsignedLongInt::from(0) {
+        return None;
+    }
+
+    let a = modulo(a, m);
+    // odd moduli are the common case, and the binary variant avoids long divisions there
+    let (g, x, _) = if m.is_odd() {
+        binary_extended_gcd(&a, m)
+    } else {
+        extended_gcd(&a, m)
+    };
+
+    if g != UnsignedLongInt::from(1) {
+        return None;
+    }
+
+    Some(x.rem_euclid(m))
+}
+
+/// returns `x mod m` for `x < BASE^2k`
+pub fn barret_reduction(x: &UnsignedLongInt, m: &BarretParameters) -> UnsignedLongInt {
     let mu = &m.mu;
-    let n = m.modulo;
+    let n = &m.modulo;
     let k = m.k;
 
     let mut q = x.shr_digits(k - 1);
@@ -96,49 +223,33 @@ fn barret_reduction<'m>(x: &UnsignedLongInt, m: &'m BarretParameters) -> Unsigne
     r
 }
 
+// the free functions below take their contexts (and reduction parameters) from `cache::global()`
+
 pub fn add_mod(a: &UnsignedLongInt, b: &UnsignedLongInt, m: &UnsignedLongInt) -> UnsignedLongInt {
-    modulo(&(a+b), m)
+    let mc = cache::global().get(m);
+    mc.add(&mc.modulo(a), &mc.modulo(b)).into()
 }
 
 pub fn sub_mod(a: &UnsignedLongInt, b: &UnsignedLongInt, m: &UnsignedLongInt) -> UnsignedLongInt {
-    let a = modulo(a, m);
-    let b = modulo(b, m);
-
-    if b < a{
-        return modulo(&(a-b), m)
-    }else{
-        return m - modulo(&(b-a), m)
-    }
+    let mc = cache::global().get(m);
+    mc.sub(&mc.modulo(a), &mc.modulo(b)).into()
 }
 
 pub fn mul_mod(a: &UnsignedLongInt, b: &UnsignedLongInt, m: &UnsignedLongInt) -> UnsignedLongInt {
-    modulo(&(modulo(a, m) * modulo(b, m)), m)
+    let mc = cache::global().get(m);
+    mc.mul(&mc.modulo(a), &mc.modulo(b)).into()
 }
 
 pub fn square_mod(a: &UnsignedLongInt, m: &UnsignedLongInt) -> UnsignedLongInt {
-    power_mod_barret(a, &UnsignedLongInt::from(2), m)
+    let mc = cache::global().get(m);
+    let a = mc.modulo(a);
+    mc.mul(&a, &a).into()
 }
 
+/// returns `a^b mod m`, the reduction parameters are sized from the modulus and reused between calls
 pub fn power_mod_barret(a: &UnsignedLongInt, b: &UnsignedLongInt, m: &UnsignedLongInt) -> UnsignedLongInt {
-    if b == &UnsignedLongInt::from(0) {
-        return UnsignedLongInt::from(1);
-    }
-    if a == &UnsignedLongInt::from(1){
-        return UnsignedLongInt::from(1);
-    }
-
-    let mut a = modulo(&a, &m);
-    let bp = BarretParameters::new(m, (a.num_digits() + 1) / 2);
-    let mut c = UnsignedLongInt::from(1);
-
-    for i in 0..(b.get_highest_set_bit().expect("must not be 0 at this point") + 1) {
-        if b.get_bit(i) {
-            c = barret_reduction(&(&c * &a), &bp);
-        }
-        a = barret_reduction(&(&a * &a), &bp)
-    }
-
-    c
+    let mc = cache::global().get(m);
+    mc.pow(&mc.modulo(a), b).into()
 }
 
 #[cfg(test)]
@@ -0,0 +1,209 @@
+use vl_big_ints::UnsignedLongInt;
+use crate::{extended_gcd, gcd, mod_inverse};
+use crate::auxiliary::significant_digits;
+use crate::context::{ModuloContext, ModuloUint};
+
+/// solves `a * x = b (mod m)`, the solutions are `base + k * step` for `k < count`,
+/// returns `(base, step, count)` with `base < step` and `step * count = m`, or `None` if there are no solutions
+pub fn solve_linear_congruence(a: &UnsignedLongInt, b: &UnsignedLongInt, m: &UnsignedLongInt) -> Option<(UnsignedLongInt, UnsignedLongInt, UnsignedLongInt)> {
+    if significant_digits(m).is_empty() {
+        return None;
+    }
+
+    let a = UnsignedLongInt::div(a, m).1;
+    let b = UnsignedLongInt::div(b, m).1;
+    if significant_digits(&a).is_empty() {
+        // every x is a solution of 0 = 0, none is of 0 = b
+        return if significant_digits(&b).is_empty() {
+            Some((UnsignedLongInt::from(0), UnsignedLongInt::from(1), m.clone()))
+        } else {
+            None
+        };
+    }
+
+    // a * x = b (mod m)  <=>  (a / g) * x = b / g (mod m / g)
+    let g = gcd(&a, m);
+    let (b_g, rem) = UnsignedLongInt::div(&b, &g);
+    if !significant_digits(&rem).is_empty() {
+        return None;
+    }
+
+    let step = m / &g;
+    let base = if significant_digits(&step) == [1] {
+        UnsignedLongInt::from(0)
+    } else {
+        let a_inv = mod_inverse(&(&a / &g), &step)?;
+        UnsignedLongInt::div(&(b_g * a_inv), &step).1
+    };
+
+    Some((base, step, g))
+}
+
+pub(crate) type Row<'m> = Vec<ModuloUint<'m>>;
+
+/// returns `x * p + y * r`
+fn combine<'m>(ctx: &'m ModuloContext<'m>, x: &ModuloUint<'m>, p: &Row<'m>, y: &ModuloUint<'m>, r: &Row<'m>) -> Row<'m> {
+    p.iter().zip(r).map(|(pi, ri)| ctx.add(&ctx.mul(x, pi), &ctx.mul(y, ri))).collect()
+}
+
+/// brings `p` and `r` with nonzero entries in column `col` to rows with `gcd(p[col], r[col])`
+/// and zero there, the transformation is unimodular so no information is lost
+pub(crate) fn eliminate<'m>(ctx: &'m ModuloContext<'m>, p: &Row<'m>, r: &Row<'m>, col: usize) -> (Row<'m>, Row<'m>) {
+    let (a, b) = (p[col].value(), r[col].value());
+    let (g, x, y) = extended_gcd(a, b);
+
+    // [x, y; b/g, -a/g] has determinant -1
+    let pivot = combine(ctx, &ctx.from_signed(&x), p, &ctx.from_signed(&y), r);
+    let rest = combine(ctx, &ctx.modulo(&(b / &g)), p, &ctx.neg(&ctx.modulo(&(a / &g))), r);
+
+    (pivot, rest)
+}
+
+/// solves the system `sum_j a[i][j] * x[j] = b[i]` over `Z/mZ` for a composite modulus as well,
+/// returns one of the solutions or `None` if there are none
+///
+/// Rows are eliminated with gcd steps instead of division by the pivot, and every pivot row `p`
+/// with a non-invertible pivot also contributes `(m / gcd(p[col], m)) * p` to the remaining rows
+/// (Howell's form of the Hermite normal form), so back substitution never has to backtrack.
+/// Free variables are set to zero.
+pub fn solve_linear_system<'m>(ctx: &'m ModuloContext<'m>, a: &[Vec<ModuloUint<'m>>], b: &[ModuloUint<'m>]) -> Option<Vec<ModuloUint<'m>>> {
+    assert_eq!(a.len(), b.len(), "every equation needs a right-hand side");
+    let n = a.first().map_or(0, |row| row.len());
+    assert!(a.iter().all(|row| row.len() == n), "all rows must have the same length");
+
+    let mut pending: Vec<Row<'m>> = a.iter().zip(b).map(|(row, bi)| {
+        let mut row = row.clone();
+        row.push(bi.clone());
+        row
+    }).collect();
+
+    let mut pivots: Vec<(usize, Row<'m>)> = Vec::new();
+    for col in 0..n {
+        let mut pivot: Option<Row<'m>> = None;
+        let mut next = Vec::with_capacity(pending.len() + 1);
+        for row in pending {
+            if row[col].is_zero() {
+                next.push(row);
+                continue;
+            }
+            pivot = Some(match pivot {
+                None => row,
+                Some(p) => {
+                    let (p, rest) = eliminate(ctx, &p, &row, col);
+                    next.push(rest);
+                    p
+                }
+            });
+        }
+
+        if let Some(p) = pivot {
+            let annihilator = ctx.modulo(&(ctx.get_modulo() / &gcd(p[col].value(), ctx.get_modulo())));
+            if !annihilator.is_zero() {
+                next.push(p.iter().map(|v| ctx.mul(&annihilator, v)).collect());
+            }
+            pivots.push((col, p));
+        }
+        pending = next;
+    }
+
+    // the remaining rows read 0 = rhs
+    if pending.iter().any(|row| !row[n].is_zero()) {
+        return None;
+    }
+
+    let mut x = vec![ctx.zero(); n];
+    for (col, row) in pivots.iter().rev() {
+        let mut rhs = row[n].clone();
+        for k in col + 1..n {
+            rhs = ctx.sub(&rhs, &ctx.mul(&row[k], &x[k]));
+        }
+        let (base, _, _) = solve_linear_congruence(row[*col].value(), rhs.value(), ctx.get_modulo())?;
+        x[*col] = ctx.modulo(&base);
+    }
+
+    let consistent = a.iter().zip(b).all(|(row, bi)| {
+        let lhs = row.iter().zip(&x).fold(ctx.zero(), |acc, (aij, xj)| ctx.add(&acc, &ctx.mul(aij, xj)));
+        &lhs == bi
+    });
+
+    if consistent {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    fn congruence(a: u64, b: u64, m: u64) -> Option<(u64, u64, u64)> {
+        let (a, b, m) = (UnsignedLongInt::from(a), UnsignedLongInt::from(b), UnsignedLongInt::from(m));
+        solve_linear_congruence(&a, &b, &m).map(|(base, step, count)| (base.digits()[0], step.digits()[0], count.digits()[0]))
+    }
+
+    fn matrix<'m>(ctx: &'m ModuloContext<'m>, rows: &[&[u64]]) -> Vec<Vec<ModuloUint<'m>>> {
+        rows.iter().map(|row| row.iter().map(|&v| ctx.from_u64(v)).collect()).collect()
+    }
+
+    #[test]
+    fn linear_congruence() -> Result<(), Box<dyn Error>> {
+        assert_eq!(congruence(3, 4, 7), Some((6, 7, 1)));
+        assert_eq!(congruence(6, 4, 10), Some((4, 5, 2)));
+        assert_eq!(congruence(6, 3, 10), None);
+        assert_eq!(congruence(0, 0, 6), Some((0, 1, 6)));
+        assert_eq!(congruence(0, 1, 6), None);
+        assert_eq!(congruence(12, 0, 6), Some((0, 1, 6)));
+
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96a")?;
+        let a = UnsignedLongInt::from_str("3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f134")?;
+        let b = UnsignedLongInt::from_str("4b11e668e24d513ec96654975ce04ca09f3eeea20501df88edba0eedce6814a2")?;
+        let (base, step, count) = solve_linear_congruence(&a, &b, &m).expect("gcd(a, m) = 2 divides b");
+        assert_eq!(count, UnsignedLongInt::from(2));
+        assert_eq!(&step * &count, m);
+        for x in [base.clone(), &base + &step] {
+            assert_eq!(UnsignedLongInt::div(&(&a * &x), &m).1, UnsignedLongInt::div(&b, &m).1);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn prime_modulus_system() {
+        let m = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&m);
+        let a = matrix(&mc, &[&[2, 1, 1], &[1, 3, 2], &[1, 0, 0]]);
+        let b: Vec<_> = [4, 5, 6].iter().map(|&v| mc.from_u64(v)).collect();
+
+        let x = solve_linear_system(&mc, &a, &b).expect("matrix is invertible");
+        let expected: Vec<_> = [6, 15, 1000003 - 23].iter().map(|&v| mc.from_u64(v)).collect();
+        assert_eq!(x, expected);
+    }
+
+    #[test]
+    fn composite_modulus_system() {
+        let m = UnsignedLongInt::from(12);
+        let mc = ModuloContext::new(&m);
+
+        // no entry of the first column is invertible, but the system has solutions
+        let a = matrix(&mc, &[&[2, 3], &[4, 1]]);
+        let b: Vec<_> = [1, 5].iter().map(|&v| mc.from_u64(v)).collect();
+        let x = solve_linear_system(&mc, &a, &b).expect("x = (5, 9) is a solution");
+        assert_eq!(mc.add(&mc.mul(&a[0][0], &x[0]), &mc.mul(&a[0][1], &x[1])), b[0]);
+        assert_eq!(mc.add(&mc.mul(&a[1][0], &x[0]), &mc.mul(&a[1][1], &x[1])), b[1]);
+
+        // 2x + 4y is always even
+        let a = matrix(&mc, &[&[2, 4], &[6, 0]]);
+        let b: Vec<_> = [3, 0].iter().map(|&v| mc.from_u64(v)).collect();
+        assert!(solve_linear_system(&mc, &a, &b).is_none());
+
+        // 4y = 0 allows y = 0, but only odd y make 2x + y = 1 solvable
+        let a = matrix(&mc, &[&[2, 1], &[0, 4]]);
+        let b: Vec<_> = [1, 0].iter().map(|&v| mc.from_u64(v)).collect();
+        let x = solve_linear_system(&mc, &a, &b).expect("x = (5, 3) is a solution");
+        assert_eq!(mc.add(&mc.mul(&a[0][0], &x[0]), &x[1]), b[0]);
+        assert_eq!(mc.mul(&a[1][1], &x[1]), b[1]);
+    }
+}
@@ -0,0 +1,477 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::*;
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::BitOps;
+use crate::context::{ModuloContext, ModuloUint};
+use crate::linear::{eliminate, Row};
+
+/// square matrices larger than this are multiplied with Strassen's algorithm
+pub const STRASSEN_THRESHOLD: usize = 64;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DimensionMismatchError;
+
+impl Display for DimensionMismatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Matrix rows have different lengths")
+    }
+}
+
+impl Error for DimensionMismatchError {}
+
+/// matrix with entries modulo the modulus of a [`ModuloContext`], stored row by row
+#[derive(Clone)]
+pub struct ModMatrix<'m> {
+    rows: usize,
+    cols: usize,
+    data: Vec<ModuloUint<'m>>,
+    context: &'m ModuloContext<'m>,
+}
+
+impl<'m> ModMatrix<'m> {
+    /// reduces every entry modulo the context's modulus, returns an error if the rows have different lengths
+    pub fn from_rows(context: &'m ModuloContext<'m>, rows: Vec<Vec<UnsignedLongInt>>) -> Result<Self, DimensionMismatchError> {
+        let cols = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != cols) {
+            return Err(DimensionMismatchError);
+        }
+
+        let data = rows.iter().flatten().map(|v| context.modulo(v)).collect();
+        Ok(Self { rows: rows.len(), cols, data, context })
+    }
+
+    pub fn zeros(context: &'m ModuloContext<'m>, rows: usize, cols: usize) -> Self {
+        Self { rows, cols, data: vec![context.zero(); rows * cols], context }
+    }
+
+    pub fn identity(context: &'m ModuloContext<'m>, n: usize) -> Self {
+        let mut result = Self::zeros(context, n, n);
+        for i in 0..n {
+            result.data[i * n + i] = context.one();
+        }
+        result
+    }
+
+    fn from_row_vecs(context: &'m ModuloContext<'m>, rows: Vec<Row<'m>>, cols: usize) -> Self {
+        Self { rows: rows.len(), cols, data: rows.into_iter().flatten().collect(), context }
+    }
+
+    fn row_vecs(&self) -> Vec<Row<'m>> {
+        (0..self.rows).map(|i| self.data[i * self.cols..(i + 1) * self.cols].to_vec()).collect()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn context(&self) -> &'m ModuloContext<'m> {
+        self.context
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    /// panics if the index is out of bounds
+    pub fn get(&self, i: usize, j: usize) -> &ModuloUint<'m> {
+        assert!(i < self.rows && j < self.cols, "index out of bounds");
+        &self.data[i * self.cols + j]
+    }
+
+    /// panics if the index is out of bounds
+    pub fn set(&mut self, i: usize, j: usize, value: ModuloUint<'m>) {
+        assert!(i < self.rows && j < self.cols, "index out of bounds");
+        self.data[i * self.cols + j] = value;
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = Self::zeros(self.context, self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                result.data[j * self.rows + i] = self.get(i, j).clone();
+            }
+        }
+        result
+    }
+
+    fn zip_with(&self, rhs: &Self, f: impl Fn(&ModuloUint<'m>, &ModuloUint<'m>) -> ModuloUint<'m>) -> Self {
+        assert!(self.rows == rhs.rows && self.cols == rhs.cols, "matrices must have the same dimensions");
+        let data = self.data.iter().zip(&rhs.data).map(|(a, b)| f(a, b)).collect();
+        Self { rows: self.rows, cols: self.cols, data, context: self.context }
+    }
+
+    /// panics if the dimensions differ
+    pub fn add(&self, rhs: &Self) -> Self {
+        self.zip_with(rhs, |a, b| self.context.add(a, b))
+    }
+
+    /// panics if the dimensions differ
+    pub fn sub(&self, rhs: &Self) -> Self {
+        self.zip_with(rhs, |a, b| self.context.sub(a, b))
+    }
+
+    /// multiplies every entry by `c`
+    pub fn scale(&self, c: &ModuloUint<'m>) -> Self {
+        let data = self.data.iter().map(|a| self.context.mul(a, c)).collect();
+        Self { rows: self.rows, cols: self.cols, data, context: self.context }
+    }
+
+    /// matrix product, uses Strassen's algorithm for square matrices larger than [`STRASSEN_THRESHOLD`]
+    ///
+    /// panics if `self.cols() != rhs.rows()`
+    pub fn mul(&self, rhs: &Self) -> Self {
+        if self.is_square() && rhs.is_square() && self.rows == rhs.rows && self.rows > STRASSEN_THRESHOLD {
+            self.mul_strassen(rhs, STRASSEN_THRESHOLD)
+        } else {
+            self.mul_schoolbook(rhs)
+        }
+    }
+
+    /// matrix product by definition
+    ///
+    /// panics if `self.cols() != rhs.rows()`
+    pub fn mul_schoolbook(&self, rhs: &Self) -> Self {
+        assert_eq!(self.cols, rhs.rows, "inner dimensions must agree");
+        let ctx = self.context;
+
+        let mut result = Self::zeros(ctx, self.rows, rhs.cols);
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                let a = self.get(i, k);
+                if a.is_zero() {
+                    continue;
+                }
+                for j in 0..rhs.cols {
+                    let c = &mut result.data[i * rhs.cols + j];
+                    *c = ctx.add(c, &ctx.mul(a, rhs.get(k, j)));
+                }
+            }
+        }
+        result
+    }
+
+    /// Strassen's algorithm with 7 instead of 8 half-size products, switches to the schoolbook
+    /// product at `threshold` rows, odd sizes are padded with zeros
+    ///
+    /// panics if the matrices are not square matrices of the same size
+    pub fn mul_strassen(&self, rhs: &Self, threshold: usize) -> Self {
+        assert!(self.is_square() && rhs.is_square() && self.rows == rhs.rows, "Strassen's algorithm needs square matrices of the same size");
+
+        let n = self.rows;
+        if n <= threshold.max(1) {
+            return self.mul_schoolbook(rhs);
+        }
+        if n % 2 == 1 {
+            let (a, b) = (self.padded(n + 1), rhs.padded(n + 1));
+            return a.mul_strassen(&b, threshold).block(0, 0, n);
+        }
+
+        let h = n / 2;
+        let (a11, a12, a21, a22) = (self.block(0, 0, h), self.block(0, h, h), self.block(h, 0, h), self.block(h, h, h));
+        let (b11, b12, b21, b22) = (rhs.block(0, 0, h), rhs.block(0, h, h), rhs.block(h, 0, h), rhs.block(h, h, h));
+
+        let m1 = (&a11 + &a22).mul_strassen(&(&b11 + &b22), threshold);
+        let m2 = (&a21 + &a22).mul_strassen(&b11, threshold);
+        let m3 = a11.mul_strassen(&(&b12 - &b22), threshold);
+        let m4 = a22.mul_strassen(&(&b21 - &b11), threshold);
+        let m5 = (&a11 + &a12).mul_strassen(&b22, threshold);
+        let m6 = (&a21 - &a11).mul_strassen(&(&b11 + &b12), threshold);
+        let m7 = (&a12 - &a22).mul_strassen(&(&b21 + &b22), threshold);
+
+        let c11 = &m1 + &m4 - &m5 + &m7;
+        let c12 = &m3 + &m5;
+        let c21 = &m2 + &m4;
+        let c22 = &m1 - &m2 + &m3 + &m6;
+
+        let mut result = Self::zeros(self.context, n, n);
+        for (block, (i0, j0)) in [(c11, (0, 0)), (c12, (0, h)), (c21, (h, 0)), (c22, (h, h))] {
+            for i in 0..h {
+                for j in 0..h {
+                    result.data[(i0 + i) * n + j0 + j] = block.get(i, j).clone();
+                }
+            }
+        }
+        result
+    }
+
+    /// square `size x size` block starting at `(i0, j0)`
+    fn block(&self, i0: usize, j0: usize, size: usize) -> Self {
+        let mut result = Self::zeros(self.context, size, size);
+        for i in 0..size {
+            for j in 0..size {
+                result.data[i * size + j] = self.get(i0 + i, j0 + j).clone();
+            }
+        }
+        result
+    }
+
+    /// extends a square matrix with zeros to `size x size`
+    fn padded(&self, size: usize) -> Self {
+        let mut result = Self::zeros(self.context, size, size);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                result.data[i * size + j] = self.get(i, j).clone();
+            }
+        }
+        result
+    }
+
+    /// returns `self^e` by square-and-multiply
+    ///
+    /// panics if the matrix is not square
+    pub fn pow<T: BitOps>(&self, e: &T) -> Self {
+        assert!(self.is_square(), "only square matrices have powers");
+
+        let mut result = Self::identity(self.context, self.rows);
+        if let Some(highest_bit) = e.get_highest_set_bit() {
+            for i in (0..=highest_bit).rev() {
+                result = &result * &result;
+                if e.get_bit(i) {
+                    result = &result * self;
+                }
+            }
+        }
+        result
+    }
+
+    /// brings `rows` to echelon form with unimodular gcd steps, which also works for composite moduli,
+    /// returns the pivot columns and whether the determinant changed its sign
+    fn echelon(ctx: &'m ModuloContext<'m>, rows: &mut [Row<'m>], cols: usize) -> (Vec<usize>, bool) {
+        let mut pivots = Vec::new();
+        let mut negated = false;
+
+        for col in 0..cols {
+            let r = pivots.len();
+            let Some(first) = (r..rows.len()).find(|&i| !rows[i][col].is_zero()) else {
+                continue;
+            };
+            if first != r {
+                rows.swap(first, r);
+                negated = !negated;
+            }
+
+            for i in r + 1..rows.len() {
+                if !rows[i][col].is_zero() {
+                    let (pivot, rest) = eliminate(ctx, &rows[r], &rows[i], col);
+                    rows[r] = pivot;
+                    rows[i] = rest;
+                    negated = !negated;
+                }
+            }
+            pivots.push(col);
+        }
+
+        (pivots, negated)
+    }
+
+    /// panics if the matrix is not square
+    pub fn det(&self) -> ModuloUint<'m> {
+        assert!(self.is_square(), "only square matrices have determinants");
+        let ctx = self.context;
+
+        let mut rows = self.row_vecs();
+        let (pivots, negated) = Self::echelon(ctx, &mut rows, self.cols);
+        if pivots.len() < self.rows {
+            return ctx.zero();
+        }
+
+        let det = (0..self.rows).fold(ctx.one(), |acc, i| ctx.mul(&acc, &rows[i][i]));
+        if negated {
+            ctx.neg(&det)
+        } else {
+            det
+        }
+    }
+
+    /// number of nonzero rows of the echelon form, which is the usual rank for prime moduli
+    pub fn rank(&self) -> usize {
+        let mut rows = self.row_vecs();
+        Self::echelon(self.context, &mut rows, self.cols).0.len()
+    }
+
+    /// returns `self^-1`, or `None` if the determinant is not invertible
+    ///
+    /// panics if the matrix is not square
+    pub fn inverse(&self) -> Option<Self> {
+        assert!(self.is_square(), "only square matrices have inverses");
+        let (ctx, n) = (self.context, self.rows);
+
+        // [self | I] -> [I | self^-1]
+        let mut rows = self.row_vecs();
+        for (i, row) in rows.iter_mut().enumerate() {
+            row.extend((0..n).map(|j| if i == j { ctx.one() } else { ctx.zero() }));
+        }
+
+        let (pivots, _) = Self::echelon(ctx, &mut rows, n);
+        if pivots.len() < n {
+            return None;
+        }
+
+        for i in (0..n).rev() {
+            let inv = ctx.inv(&rows[i][i])?;
+            rows[i] = rows[i].iter().map(|v| ctx.mul(v, &inv)).collect();
+            for k in 0..i {
+                let factor = rows[k][i].clone();
+                if !factor.is_zero() {
+                    rows[k] = rows[k].iter().zip(&rows[i]).map(|(a, b)| ctx.sub(a, &ctx.mul(&factor, b))).collect();
+                }
+            }
+        }
+
+        let rows = rows.into_iter().map(|row| row[n..].to_vec()).collect();
+        Some(Self::from_row_vecs(ctx, rows, n))
+    }
+}
+
+impl From<ModMatrix<'_>> for Vec<Vec<UnsignedLongInt>> {
+    fn from(value: ModMatrix<'_>) -> Self {
+        value.row_vecs().into_iter().map(|row| row.into_iter().map(UnsignedLongInt::from).collect()).collect()
+    }
+}
+
+impl Debug for ModMatrix<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rows: Vec<Vec<String>> = self.row_vecs().iter().map(|row| row.iter().map(|v| v.to_string()).collect()).collect();
+        write!(f, "{:?} (mod {})", rows, self.context.get_modulo())
+    }
+}
+
+impl PartialEq for ModMatrix<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rows == other.rows && self.cols == other.cols && self.data == other.data
+    }
+}
+
+impl Eq for ModMatrix<'_> {}
+
+#[auto_impl_ops::auto_ops]
+impl<'m> Add<&ModMatrix<'m>> for &ModMatrix<'m>
+{
+    type Output = ModMatrix<'m>;
+    fn add(self, rhs: &ModMatrix<'m>) -> Self::Output {
+        ModMatrix::add(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'m> Sub<&ModMatrix<'m>> for &ModMatrix<'m>
+{
+    type Output = ModMatrix<'m>;
+    fn sub(self, rhs: &ModMatrix<'m>) -> Self::Output {
+        ModMatrix::sub(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'m> Mul<&ModMatrix<'m>> for &ModMatrix<'m>
+{
+    type Output = ModMatrix<'m>;
+    fn mul(self, rhs: &ModMatrix<'m>) -> Self::Output {
+        ModMatrix::mul(self, rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    fn matrix<'m>(ctx: &'m ModuloContext<'m>, rows: &[&[u64]]) -> ModMatrix<'m> {
+        let rows = rows.iter().map(|row| row.iter().map(|&v| UnsignedLongInt::from(v)).collect()).collect();
+        ModMatrix::from_rows(ctx, rows).expect("rows have the same length")
+    }
+
+    #[test]
+    fn basic_arithmetic() {
+        let m = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&m);
+        let a = matrix(&mc, &[&[1, 2, 3], &[4, 5, 6]]);
+        let b = matrix(&mc, &[&[7, 8], &[9, 10], &[11, 12]]);
+
+        assert_eq!(&a * &b, matrix(&mc, &[&[58, 64], &[139, 154]]));
+        assert_eq!(a.transpose(), matrix(&mc, &[&[1, 4], &[2, 5], &[3, 6]]));
+        assert_eq!(&a + &a - &a, a);
+        assert_eq!(a.scale(&mc.from_u64(2)), &a + &a);
+        assert_eq!(Vec::<Vec<UnsignedLongInt>>::from(a.transpose().transpose()), Vec::<Vec<UnsignedLongInt>>::from(a));
+
+        // rows without columns survive the round trip
+        let empty_rows = vec![Vec::new(); 3];
+        assert_eq!(Vec::<Vec<UnsignedLongInt>>::from(ModMatrix::from_rows(&mc, empty_rows.clone()).expect("equal lengths")), empty_rows);
+
+        let ragged = vec![vec![UnsignedLongInt::from(1)], vec![]];
+        assert_eq!(ModMatrix::from_rows(&mc, ragged).err(), Some(DimensionMismatchError));
+    }
+
+    #[test]
+    fn fibonacci_power() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str("7fffffffffffffffffffffffffffffff")?;
+        let mc = ModuloContext::new(&m);
+        let q = matrix(&mc, &[&[1, 1], &[1, 0]]);
+
+        // F(100) = 354224848179261915075
+        let f = q.pow(&UnsignedLongInt::from(100));
+        assert_eq!(f.get(0, 1).value(), &UnsignedLongInt::from_str("1333db76a7c594bfc3")?);
+        assert_eq!(q.pow(&UnsignedLongInt::from(0)), ModMatrix::identity(&mc, 2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn determinant_rank_inverse() {
+        let m = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&m);
+        let a = matrix(&mc, &[&[2, 1, 1], &[1, 3, 2], &[1, 0, 0]]);
+        assert_eq!(a.det(), mc.from_u64(1000002));
+        assert_eq!(a.rank(), 3);
+        let a_inv = a.inverse().expect("determinant is invertible");
+        assert_eq!(&a * &a_inv, ModMatrix::identity(&mc, 3));
+
+        let singular = matrix(&mc, &[&[1, 2], &[2, 4]]);
+        assert!(singular.det().is_zero());
+        assert_eq!(singular.rank(), 1);
+        assert!(singular.inverse().is_none());
+    }
+
+    #[test]
+    fn composite_modulus() {
+        let m = UnsignedLongInt::from(12);
+        let mc = ModuloContext::new(&m);
+
+        // 2 * 1 - 3 * 4 = -10
+        assert_eq!(matrix(&mc, &[&[2, 3], &[4, 1]]).det(), mc.from_u64(2));
+
+        // no entry of the first column is invertible, but the determinant is
+        let a = matrix(&mc, &[&[2, 3], &[3, 5]]);
+        assert_eq!(a.det(), mc.one());
+        let a_inv = a.inverse().expect("determinant is invertible");
+        assert_eq!(&a_inv * &a, ModMatrix::identity(&mc, 2));
+
+        assert!(matrix(&mc, &[&[2, 3], &[4, 1]]).inverse().is_none());
+    }
+
+    #[test]
+    fn strassen_matches_schoolbook() {
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f").expect("valid hex");
+        let mc = ModuloContext::new(&m);
+
+        let n = 7;
+        let mut seed = 1u64;
+        let mut next = || {
+            seed = seed.wrapping_mul(0x9e3779b97f4a7c15).wrapping_add(1);
+            UnsignedLongInt::from(seed)
+        };
+        let a = ModMatrix::from_rows(&mc, (0..n).map(|_| (0..n).map(|_| next()).collect()).collect()).expect("square");
+        let b = ModMatrix::from_rows(&mc, (0..n).map(|_| (0..n).map(|_| next()).collect()).collect()).expect("square");
+
+        let expected = a.mul_schoolbook(&b);
+        for threshold in [1, 2, 3, 8] {
+            assert_eq!(a.mul_strassen(&b, threshold), expected);
+        }
+    }
+}
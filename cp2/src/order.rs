@@ -0,0 +1,228 @@
+use vl_big_ints::UnsignedLongInt;
+use crate::lcm;
+use crate::auxiliary::{bit_length, significant_digits};
+use crate::context::{ModuloContext, ModuloUint};
+use crate::factor::{factorize, is_probable_prime};
+
+/// prime factorization as `(p, e)` pairs, see [`factorize`]
+pub type Factorization = [(UnsignedLongInt, u32)];
+
+fn factors_or_factorize(n: &UnsignedLongInt, factors: Option<&Factorization>) -> Vec<(UnsignedLongInt, u32)> {
+    match factors {
+        Some(factors) => factors.to_vec(),
+        None => factorize(n),
+    }
+}
+
+fn product(factors: &Factorization) -> UnsignedLongInt {
+    factors.iter().fold(UnsignedLongInt::from(1), |acc, (p, e)| acc * p.pow(&UnsignedLongInt::from(*e as u64)))
+}
+
+/// `phi(p^e)` and `lambda(p^e)` for every prime power of the factorization
+fn prime_power_orders(factors: &Factorization) -> impl Iterator<Item = (UnsignedLongInt, UnsignedLongInt)> + '_ {
+    let one = UnsignedLongInt::from(1);
+    let two = UnsignedLongInt::from(2);
+    factors.iter().map(move |(p, e)| {
+        // phi(p^e) = p^(e-1) * (p - 1), lambda(2^e) = 2^(e-2) for e >= 3
+        let phi = p.pow(&UnsignedLongInt::from(*e as u64 - 1)) * (p - &one);
+        let lambda = if p == &two && *e >= 3 { phi.shr(&one) } else { phi.clone() };
+        (phi, lambda)
+    })
+}
+
+/// Euler's totient `phi(n)`, the number of residues modulo `n` coprime to `n`
+///
+/// `factors` is the factorization of `n`, it is computed if not given
+pub fn euler_phi(n: &UnsignedLongInt, factors: Option<&Factorization>) -> UnsignedLongInt {
+    let factors = factors_or_factorize(n, factors);
+    prime_power_orders(&factors).fold(UnsignedLongInt::from(1), |acc, (phi, _)| acc * phi)
+}
+
+/// Carmichael's function `lambda(n)`, the exponent of the multiplicative group modulo `n`
+///
+/// `factors` is the factorization of `n`, it is computed if not given
+pub fn carmichael_lambda(n: &UnsignedLongInt, factors: Option<&Factorization>) -> UnsignedLongInt {
+    let factors = factors_or_factorize(n, factors);
+    prime_power_orders(&factors).fold(UnsignedLongInt::from(1), |acc, (_, lambda)| lcm(&acc, &lambda))
+}
+
+/// factorization of `phi(m)`, the order of the multiplicative group, computed from scratch if not given
+fn group_order_factors(ctx: &ModuloContext, factors: Option<&Factorization>) -> Vec<(UnsignedLongInt, u32)> {
+    match factors {
+        Some(factors) => factors.to_vec(),
+        None => factorize(&euler_phi(ctx.get_modulo(), None)),
+    }
+}
+
+/// the smallest `k > 0` with `a^k = 1`, or `None` if `a` is not invertible
+///
+/// `factors` is the factorization of the group order `phi(m)` (`p - 1` for a prime modulus `p`),
+/// it is computed if not given
+pub fn multiplicative_order(a: &ModuloUint, ctx: &ModuloContext, factors: Option<&Factorization>) -> Option<UnsignedLongInt> {
+    ctx.check_context(&[a]).ok()?;
+
+    let factors = group_order_factors(ctx, factors);
+    let mut order = product(&factors);
+    if !a.pow(&order).is_one() {
+        return None;
+    }
+
+    // strip every prime from phi(m) as long as the power stays one
+    for (p, e) in &factors {
+        for _ in 0..*e {
+            let (reduced, _) = UnsignedLongInt::div(&order, p);
+            if !a.pow(&reduced).is_one() {
+                break;
+            }
+            order = reduced;
+        }
+    }
+
+    Some(order)
+}
+
+/// checks whether `g` generates the multiplicative group, which is only cyclic for moduli
+/// `1, 2, 4, p^k` and `2 p^k`
+///
+/// `factors` is the factorization of the group order `phi(m)` (`p - 1` for a prime modulus `p`),
+/// it is computed if not given
+pub fn is_primitive_root(g: &ModuloUint, ctx: &ModuloContext, factors: Option<&Factorization>) -> bool {
+    if ctx.check_context(&[g]).is_err() {
+        return false;
+    }
+
+    is_generator(g, &group_order_factors(ctx, factors))
+}
+
+/// `g` has order `phi(m)`, which no element has if the group is not cyclic
+fn is_generator(g: &ModuloUint, phi_factors: &Factorization) -> bool {
+    let phi = product(phi_factors);
+    g.pow(&phi).is_one() && phi_factors.iter().all(|(p, _)| !g.pow(&(&phi / p)).is_one())
+}
+
+/// checks whether `m` is `1, 2, 4, p^k` or `2 p^k` for an odd prime `p`: a prime `p` with `k > 1`
+/// also divides `phi(m)`, so only the primes of `phi(m)` and `m` itself have to be tried
+fn is_cyclic(m: &UnsignedLongInt, phi_factors: &Factorization) -> bool {
+    let two = UnsignedLongInt::from(2);
+    let odd = match (m.get_bit(0), m.get_bit(1)) {
+        (true, _) => m.clone(),
+        (false, true) => m.shr(&UnsignedLongInt::from(1)),
+        (false, false) => return significant_digits(m) == [4],
+    };
+    if bit_length(&odd) <= 1 {
+        return true;
+    }
+
+    let is_power_of = |p: &UnsignedLongInt| {
+        let mut r = odd.clone();
+        loop {
+            let (q, rem) = UnsignedLongInt::div(&r, p);
+            if !significant_digits(&rem).is_empty() {
+                return false;
+            }
+            if bit_length(&q) <= 1 {
+                return true;
+            }
+            r = q;
+        }
+    };
+
+    phi_factors.iter().any(|(p, _)| p != &two && is_power_of(p)) || is_probable_prime(&odd)
+}
+
+/// the smallest primitive root, or `None` if the multiplicative group is not cyclic
+///
+/// `factors` is the factorization of the group order `phi(m)` (`p - 1` for a prime modulus `p`),
+/// it is computed if not given
+pub fn find_primitive_root<'m>(ctx: &'m ModuloContext<'m>, factors: Option<&Factorization>) -> Option<ModuloUint<'m>> {
+    if bit_length(ctx.get_modulo()) <= 1 {
+        return Some(ctx.zero());
+    }
+
+    let factors = group_order_factors(ctx, factors);
+    if !is_cyclic(ctx.get_modulo(), &factors) {
+        return None;
+    }
+
+    (1..).map(|g| ctx.from_u64(g)).find(|g| is_generator(g, &factors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    #[test]
+    fn totient_and_carmichael() {
+        let cases = [(1, 1, 1), (2, 1, 1), (8, 4, 2), (9, 6, 6), (15, 8, 4), (32, 16, 8), (561, 320, 80), (1000003, 1000002, 1000002)];
+        for (n, phi, lambda) in cases {
+            let n = UnsignedLongInt::from(n);
+            assert_eq!(euler_phi(&n, None), UnsignedLongInt::from(phi));
+            assert_eq!(carmichael_lambda(&n, None), UnsignedLongInt::from(lambda));
+        }
+
+        let factors = [(UnsignedLongInt::from(3), 28)];
+        let n = UnsignedLongInt::from(3).pow(&UnsignedLongInt::from(28));
+        let phi = UnsignedLongInt::from(2) * UnsignedLongInt::from(3).pow(&UnsignedLongInt::from(27));
+        assert_eq!(euler_phi(&n, Some(&factors)), phi);
+        assert_eq!(euler_phi(&n, None), phi);
+    }
+
+    #[test]
+    fn orders() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&m);
+        assert_eq!(multiplicative_order(&mc.from_u64(2), &mc, None), Some(UnsignedLongInt::from(1000002)));
+        assert_eq!(multiplicative_order(&mc.from_u64(4), &mc, None), Some(UnsignedLongInt::from(500001)));
+        assert_eq!(multiplicative_order(&mc.one(), &mc, None), Some(UnsignedLongInt::from(1)));
+        assert_eq!(multiplicative_order(&mc.zero(), &mc, None), None);
+
+        let m = UnsignedLongInt::from_str("ffffffff00000001")?;
+        let mc = ModuloContext::new(&m);
+        // p - 1 = 2^32 * 3 * 5 * 17 * 257 * 65537
+        let factors: Vec<_> = [(2, 32), (3, 1), (5, 1), (17, 1), (257, 1), (65537, 1)].iter().map(|&(p, e)| (UnsignedLongInt::from(p), e)).collect();
+        let order = UnsignedLongInt::from_str("ffffffff00000000")?;
+        assert_eq!(multiplicative_order(&mc.from_u64(7), &mc, Some(&factors)), Some(order));
+        assert_eq!(multiplicative_order(&mc.from_u64(49), &mc, Some(&factors)), Some(UnsignedLongInt::from_str("7fffffff80000000")?));
+
+        Ok(())
+    }
+
+    #[test]
+    fn primitive_roots() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str("ffffffff00000001")?;
+        let mc = ModuloContext::new(&m);
+        assert_eq!(find_primitive_root(&mc, None), Some(mc.from_u64(7)));
+        assert!(is_primitive_root(&mc.from_u64(7), &mc, None));
+        assert!(!is_primitive_root(&mc.from_u64(4), &mc, None));
+
+        // 2 * 3^5, the group is cyclic
+        let m = UnsignedLongInt::from(486);
+        let mc = ModuloContext::new(&m);
+        let g = find_primitive_root(&mc, None).expect("2 p^k has primitive roots");
+        assert_eq!(g, mc.from_u64(5));
+        assert_eq!(multiplicative_order(&g, &mc, None), Some(UnsignedLongInt::from(162)));
+
+        let m = UnsignedLongInt::from(15);
+        let mc = ModuloContext::new(&m);
+        assert!(find_primitive_root(&mc, None).is_none());
+        assert!(!is_primitive_root(&mc.from_u64(2), &mc, None));
+
+        for (m, g) in [(4, Some(3)), (8, None), (9, Some(2)), (50, Some(3)), (100, None)] {
+            let m = UnsignedLongInt::from(m);
+            let mc = ModuloContext::new(&m);
+            assert_eq!(find_primitive_root(&mc, None), g.map(|g| mc.from_u64(g)));
+        }
+
+        // safe prime p = 2q + 1, the group order is factored by the caller
+        let p = UnsignedLongInt::from_str("100000000000001e361823fbd97239c6eb791f7797bcb813282c9b0733eed02c3")?;
+        let q = UnsignedLongInt::from_str("80000000000000f1b0c11fdecb91ce375bc8fbbcbde5c0994164d8399f768161")?;
+        let mc = ModuloContext::new(&p);
+        let factors = [(UnsignedLongInt::from(2), 1), (q.clone(), 1)];
+        assert_eq!(find_primitive_root(&mc, Some(&factors)), Some(mc.from_u64(2)));
+        assert_eq!(multiplicative_order(&mc.from_u64(4), &mc, Some(&factors)), Some(q));
+
+        Ok(())
+    }
+}
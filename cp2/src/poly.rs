@@ -0,0 +1,474 @@
+use std::fmt::{Debug, Formatter};
+use std::ops::*;
+use vl_big_ints::UnsignedLongInt;
+use crate::context::{ModuloContext, ModuloUint};
+
+/// polynomials longer than this are multiplied with Karatsuba's algorithm
+pub const KARATSUBA_THRESHOLD: usize = 32;
+
+/// point sets up to this size are evaluated one by one instead of with a remainder tree
+const MULTIPOINT_LEAF: usize = 8;
+
+/// polynomial with coefficients modulo the modulus of a [`ModuloContext`]
+#[derive(Clone)]
+pub struct ModPoly<'m> {
+    /// lowest degree first, without trailing zeros
+    coeffs: Vec<ModuloUint<'m>>,
+    context: &'m ModuloContext<'m>,
+}
+
+impl<'m> ModPoly<'m> {
+    /// reduces the coefficients, given lowest degree first
+    pub fn new(context: &'m ModuloContext<'m>, coeffs: &[UnsignedLongInt]) -> Self {
+        Self::from_coeffs(context, coeffs.iter().map(|c| context.modulo(c)).collect())
+    }
+
+    /// coefficients are given lowest degree first
+    pub fn from_coeffs(context: &'m ModuloContext<'m>, mut coeffs: Vec<ModuloUint<'m>>) -> Self {
+        while coeffs.last().is_some_and(|c| c.is_zero()) {
+            coeffs.pop();
+        }
+        Self { coeffs, context }
+    }
+
+    pub fn zero(context: &'m ModuloContext<'m>) -> Self {
+        Self { coeffs: Vec::new(), context }
+    }
+
+    /// returns `c * x^k`
+    pub fn monomial(context: &'m ModuloContext<'m>, c: ModuloUint<'m>, k: usize) -> Self {
+        let mut coeffs = vec![context.zero(); k];
+        coeffs.push(c);
+        Self::from_coeffs(context, coeffs)
+    }
+
+    pub fn context(&self) -> &'m ModuloContext<'m> {
+        self.context
+    }
+
+    /// coefficients, lowest degree first, without trailing zeros
+    pub fn coeffs(&self) -> &[ModuloUint<'m>] {
+        &self.coeffs
+    }
+
+    /// `None` for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.coeffs.len().checked_sub(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    /// leading coefficient, `None` for the zero polynomial
+    pub fn leading(&self) -> Option<&ModuloUint<'m>> {
+        self.coeffs.last()
+    }
+
+    pub fn add(&self, rhs: &Self) -> Self {
+        Self::from_coeffs(self.context, add_slices(self.context, &self.coeffs, &rhs.coeffs))
+    }
+
+    pub fn sub(&self, rhs: &Self) -> Self {
+        Self::from_coeffs(self.context, sub_slices(self.context, &self.coeffs, &rhs.coeffs))
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::zero(self.context).sub(self)
+    }
+
+    /// multiplies every coefficient by `c`
+    pub fn scale(&self, c: &ModuloUint<'m>) -> Self {
+        Self::from_coeffs(self.context, self.coeffs.iter().map(|a| self.context.mul(a, c)).collect())
+    }
+
+    /// product, uses Karatsuba's algorithm for polynomials longer than [`KARATSUBA_THRESHOLD`]
+    pub fn mul(&self, rhs: &Self) -> Self {
+        self.mul_karatsuba(rhs, KARATSUBA_THRESHOLD)
+    }
+
+    /// product by definition
+    pub fn mul_schoolbook(&self, rhs: &Self) -> Self {
+        Self::from_coeffs(self.context, mul_schoolbook(self.context, &self.coeffs, &rhs.coeffs))
+    }
+
+    /// Karatsuba's algorithm with 3 instead of 4 half-size products, switches to the schoolbook
+    /// product below `threshold` coefficients
+    pub fn mul_karatsuba(&self, rhs: &Self, threshold: usize) -> Self {
+        Self::from_coeffs(self.context, karatsuba(self.context, &self.coeffs, &rhs.coeffs, threshold.max(2)))
+    }
+
+    /// returns `(q, r)` with `self = q * d + r` and `deg r < deg d`,
+    /// or `None` if `d` is zero or its leading coefficient is not invertible
+    pub fn divrem(&self, d: &Self) -> Option<(Self, Self)> {
+        let ctx = self.context;
+        let lead_inv = ctx.inv(d.leading()?)?;
+        let n = d.coeffs.len();
+        if self.coeffs.len() < n {
+            return Some((Self::zero(ctx), self.clone()));
+        }
+
+        let mut r = self.coeffs.clone();
+        let mut q = vec![ctx.zero(); r.len() - n + 1];
+        for i in (0..q.len()).rev() {
+            let c = ctx.mul(&r[i + n - 1], &lead_inv);
+            if c.is_zero() {
+                continue;
+            }
+            for (j, dj) in d.coeffs.iter().enumerate() {
+                r[i + j] = ctx.sub(&r[i + j], &ctx.mul(&c, dj));
+            }
+            q[i] = c;
+        }
+        r.truncate(n - 1);
+
+        Some((Self::from_coeffs(ctx, q), Self::from_coeffs(ctx, r)))
+    }
+
+    /// returns `self mod d`, see [`ModPoly::divrem`]
+    pub fn rem(&self, d: &Self) -> Option<Self> {
+        self.divrem(d).map(|(_, r)| r)
+    }
+
+    /// divides by the leading coefficient, `None` if it is not invertible
+    pub fn monic(&self) -> Option<Self> {
+        match self.leading() {
+            None => Some(self.clone()),
+            Some(lead) => Some(self.scale(&self.context.inv(lead)?)),
+        }
+    }
+
+    /// monic greatest common divisor by Euclid's algorithm, or `None` if a leading coefficient
+    /// along the way is not invertible, which can only happen for composite moduli
+    pub fn gcd(&self, rhs: &Self) -> Option<Self> {
+        let (mut a, mut b) = (self.clone(), rhs.clone());
+        while !b.is_zero() {
+            let r = a.rem(&b)?;
+            a = b;
+            b = r;
+        }
+        a.monic()
+    }
+
+    /// value at `x` by Horner's scheme
+    pub fn eval(&self, x: &ModuloUint<'m>) -> ModuloUint<'m> {
+        let ctx = self.context;
+        self.coeffs.iter().rev().fold(ctx.zero(), |acc, c| ctx.add(&ctx.mul(&acc, x), c))
+    }
+
+    /// values at all `points`: builds the subproduct tree of the linear factors once, then reduces
+    /// modulo its nodes down to the leaves (remainder tree)
+    pub fn eval_many(&self, points: &[ModuloUint<'m>]) -> Vec<ModuloUint<'m>> {
+        if points.len() <= MULTIPOINT_LEAF {
+            return points.iter().map(|x| self.eval(x)).collect();
+        }
+
+        let tree = SubproductTree::new(self.context, points);
+        let mut values = Vec::with_capacity(points.len());
+        tree.remainders(&self.rem(&tree.poly).expect("vanishing polynomials are monic"), points, &mut values);
+        values
+    }
+
+    /// the polynomial of degree below `points.len()` through all `(x, y)` points by Lagrange's formula,
+    /// or `None` if the differences of some `x` are not invertible (e.g. a repeated `x`)
+    pub fn interpolate(context: &'m ModuloContext<'m>, points: &[(ModuloUint<'m>, ModuloUint<'m>)]) -> Option<Self> {
+        let xs: Vec<_> = points.iter().map(|(x, _)| x.clone()).collect();
+        let m = vanishing(context, &xs);
+
+        // y_i * prod_{j != i} (x - x_j) / (x_i - x_j)
+        let mut result = Self::zero(context);
+        for (x, y) in points {
+            let linear = Self::from_coeffs(context, vec![context.neg(x), context.one()]);
+            let (basis, _) = m.divrem(&linear)?;
+            let weight = context.div(y, &basis.eval(x))?;
+            result = result.add(&basis.scale(&weight));
+        }
+
+        Some(result)
+    }
+
+    /// formal derivative
+    pub fn derivative(&self) -> Self {
+        let ctx = self.context;
+        let coeffs = self.coeffs.iter().enumerate().skip(1).map(|(i, c)| ctx.mul(c, &ctx.from_u64(i as u64))).collect();
+        Self::from_coeffs(ctx, coeffs)
+    }
+
+    /// returns `self(g) mod h` by Horner's scheme, reducing after every step,
+    /// or `None` if the leading coefficient of `h` is not invertible
+    pub fn compose_mod(&self, g: &Self, h: &Self) -> Option<Self> {
+        let g = g.rem(h)?;
+        let mut result = Self::zero(self.context);
+        for c in self.coeffs.iter().rev() {
+            let constant = Self::from_coeffs(self.context, vec![c.clone()]);
+            result = result.mul(&g).add(&constant).rem(h)?;
+        }
+        Some(result)
+    }
+}
+
+/// returns `prod (x - x_i)`
+fn vanishing<'m>(ctx: &'m ModuloContext<'m>, points: &[ModuloUint<'m>]) -> ModPoly<'m> {
+    match points {
+        [] => ModPoly::from_coeffs(ctx, vec![ctx.one()]),
+        [x] => ModPoly::from_coeffs(ctx, vec![ctx.neg(x), ctx.one()]),
+        _ => {
+            let (left, right) = points.split_at(points.len() / 2);
+            vanishing(ctx, left).mul(&vanishing(ctx, right))
+        }
+    }
+}
+
+/// products of the linear factors `x - x_i` over halves of the points, the root holds all of them
+struct SubproductTree<'m> {
+    poly: ModPoly<'m>,
+    children: Option<Box<[SubproductTree<'m>; 2]>>,
+}
+
+impl<'m> SubproductTree<'m> {
+    fn new(ctx: &'m ModuloContext<'m>, points: &[ModuloUint<'m>]) -> Self {
+        if points.len() <= MULTIPOINT_LEAF {
+            return Self { poly: vanishing(ctx, points), children: None };
+        }
+
+        let (left, right) = points.split_at(points.len() / 2);
+        let (left, right) = (Self::new(ctx, left), Self::new(ctx, right));
+        let poly = &left.poly * &right.poly;
+        Self { poly, children: Some(Box::new([left, right])) }
+    }
+
+    /// appends the values of `f` at `points`, `f` is already reduced modulo this node
+    fn remainders(&self, f: &ModPoly<'m>, points: &[ModuloUint<'m>], values: &mut Vec<ModuloUint<'m>>) {
+        match &self.children {
+            None => values.extend(points.iter().map(|x| f.eval(x))),
+            Some(children) => {
+                let (left, right) = points.split_at(points.len() / 2);
+                for (child, half) in children.iter().zip([left, right]) {
+                    let g = f.rem(&child.poly).expect("vanishing polynomials are monic");
+                    child.remainders(&g, half, values);
+                }
+            }
+        }
+    }
+}
+
+fn add_slices<'m>(ctx: &'m ModuloContext<'m>, a: &[ModuloUint<'m>], b: &[ModuloUint<'m>]) -> Vec<ModuloUint<'m>> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut result = long.to_vec();
+    for (r, s) in result.iter_mut().zip(short) {
+        *r = ctx.add(r, s);
+    }
+    result
+}
+
+fn sub_slices<'m>(ctx: &'m ModuloContext<'m>, a: &[ModuloUint<'m>], b: &[ModuloUint<'m>]) -> Vec<ModuloUint<'m>> {
+    let mut result = a.to_vec();
+    result.resize(a.len().max(b.len()), ctx.zero());
+    for (r, s) in result.iter_mut().zip(b) {
+        *r = ctx.sub(r, s);
+    }
+    result
+}
+
+fn mul_schoolbook<'m>(ctx: &'m ModuloContext<'m>, a: &[ModuloUint<'m>], b: &[ModuloUint<'m>]) -> Vec<ModuloUint<'m>> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+
+    let mut result = vec![ctx.zero(); a.len() + b.len() - 1];
+    for (i, ai) in a.iter().enumerate() {
+        if ai.is_zero() {
+            continue;
+        }
+        for (j, bj) in b.iter().enumerate() {
+            result[i + j] = ctx.add(&result[i + j], &ctx.mul(ai, bj));
+        }
+    }
+    result
+}
+
+fn karatsuba<'m>(ctx: &'m ModuloContext<'m>, a: &[ModuloUint<'m>], b: &[ModuloUint<'m>], threshold: usize) -> Vec<ModuloUint<'m>> {
+    if a.len().min(b.len()) < threshold {
+        return mul_schoolbook(ctx, a, b);
+    }
+
+    // a = a0 + x^h a1, b = b0 + x^h b1
+    let h = a.len().max(b.len()) / 2;
+    let (a0, a1) = a.split_at(h.min(a.len()));
+    let (b0, b1) = b.split_at(h.min(b.len()));
+
+    let z0 = karatsuba(ctx, a0, b0, threshold);
+    let z2 = karatsuba(ctx, a1, b1, threshold);
+    let z1 = karatsuba(ctx, &add_slices(ctx, a0, a1), &add_slices(ctx, b0, b1), threshold);
+    let z1 = sub_slices(ctx, &sub_slices(ctx, &z1, &z0), &z2);
+
+    let mut result = vec![ctx.zero(); a.len() + b.len() - 1];
+    for (shift, z) in [(0, &z0), (h, &z1), (2 * h, &z2)] {
+        for (i, c) in z.iter().enumerate() {
+            // the middle term may carry trailing zeros beyond the product's length
+            if let Some(r) = result.get_mut(shift + i) {
+                *r = ctx.add(r, c);
+            }
+        }
+    }
+    result
+}
+
+impl Debug for ModPoly<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let coeffs: Vec<String> = self.coeffs.iter().map(|c| c.to_string()).collect();
+        write!(f, "{:?} (mod {})", coeffs, self.context.get_modulo())
+    }
+}
+
+impl PartialEq for ModPoly<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.coeffs == other.coeffs
+    }
+}
+
+impl Eq for ModPoly<'_> {}
+
+#[auto_impl_ops::auto_ops]
+impl<'m> Add<&ModPoly<'m>> for &ModPoly<'m>
+{
+    type Output = ModPoly<'m>;
+    fn add(self, rhs: &ModPoly<'m>) -> Self::Output {
+        ModPoly::add(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'m> Sub<&ModPoly<'m>> for &ModPoly<'m>
+{
+    type Output = ModPoly<'m>;
+    fn sub(self, rhs: &ModPoly<'m>) -> Self::Output {
+        ModPoly::sub(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'m> Mul<&ModPoly<'m>> for &ModPoly<'m>
+{
+    type Output = ModPoly<'m>;
+    fn mul(self, rhs: &ModPoly<'m>) -> Self::Output {
+        ModPoly::mul(self, rhs)
+    }
+}
+
+impl<'m> Neg for ModPoly<'m>
+{
+    type Output = ModPoly<'m>;
+    fn neg(self) -> Self::Output {
+        ModPoly::neg(&self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    fn poly<'m>(ctx: &'m ModuloContext<'m>, coeffs: &[u64]) -> ModPoly<'m> {
+        ModPoly::from_coeffs(ctx, coeffs.iter().map(|&c| ctx.from_u64(c)).collect())
+    }
+
+    fn random_poly<'m>(ctx: &'m ModuloContext<'m>, len: usize, seed: &mut u64) -> ModPoly<'m> {
+        let coeffs = (0..len).map(|_| {
+            *seed = seed.wrapping_mul(0x9e3779b97f4a7c15).wrapping_add(1);
+            ctx.modulo(&UnsignedLongInt::from(*seed))
+        }).collect();
+        ModPoly::from_coeffs(ctx, coeffs)
+    }
+
+    #[test]
+    fn arithmetic() {
+        let m = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&m);
+        let a = poly(&mc, &[1, 2, 3]);
+        let b = poly(&mc, &[5, 0, 1000002]);
+
+        assert_eq!(&a + &b, poly(&mc, &[6, 2, 2]));
+        assert_eq!(&a - &a, ModPoly::zero(&mc));
+        assert_eq!((&a + &b).degree(), Some(2));
+        assert_eq!((&a * &b), poly(&mc, &[5, 10, 14, 1000001, 1000000]));
+        assert_eq!(-a.clone() + &a, ModPoly::zero(&mc));
+        assert_eq!(a.derivative(), poly(&mc, &[2, 6]));
+        assert_eq!(a.eval(&mc.from_u64(2)), mc.from_u64(17));
+    }
+
+    #[test]
+    fn karatsuba_matches_schoolbook() -> Result<(), Box<dyn Error>> {
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+        let mc = ModuloContext::new(&m);
+        let mut seed = 1;
+        let a = random_poly(&mc, 45, &mut seed);
+        let b = random_poly(&mc, 30, &mut seed);
+
+        let expected = a.mul_schoolbook(&b);
+        for threshold in [2, 3, 8, 64] {
+            assert_eq!(a.mul_karatsuba(&b, threshold), expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn division_and_gcd() {
+        let m = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&m);
+        let mut seed = 7;
+        let a = random_poly(&mc, 12, &mut seed);
+        let d = random_poly(&mc, 5, &mut seed);
+
+        let (q, r) = a.divrem(&d).expect("leading coefficient is invertible");
+        assert_eq!(&q * &d + &r, a);
+        assert!(r.degree() < d.degree());
+        assert!(a.divrem(&ModPoly::zero(&mc)).is_none());
+
+        // (x - 1)(x - 2) and (x - 1)(x - 3)
+        let f = poly(&mc, &[2, 1000000, 1]).scale(&mc.from_u64(5));
+        let g = poly(&mc, &[3, 1000002 - 3, 1]);
+        assert_eq!(f.gcd(&g), Some(poly(&mc, &[1000002, 1])));
+
+        // x mod 4: 2 is not invertible
+        let m = UnsignedLongInt::from(4);
+        let mc = ModuloContext::new(&m);
+        assert!(poly(&mc, &[1, 1]).divrem(&poly(&mc, &[1, 2])).is_none());
+    }
+
+    #[test]
+    fn evaluation_and_interpolation() {
+        let m = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&m);
+        let mut seed = 3;
+        let f = random_poly(&mc, 20, &mut seed);
+
+        let points: Vec<_> = (0..25).map(|i| mc.from_u64(i * i + 1)).collect();
+        let expected: Vec<_> = points.iter().map(|x| f.eval(x)).collect();
+        assert_eq!(f.eval_many(&points), expected);
+
+        // any 20 points recover the polynomial, as in Shamir's secret sharing
+        let shares: Vec<_> = points.iter().zip(expected).skip(5).map(|(x, y)| (x.clone(), y)).collect();
+        assert_eq!(ModPoly::interpolate(&mc, &shares), Some(f));
+
+        let repeated = vec![(mc.from_u64(1), mc.from_u64(2)), (mc.from_u64(1), mc.from_u64(3))];
+        assert!(ModPoly::interpolate(&mc, &repeated).is_none());
+    }
+
+    #[test]
+    fn modular_composition() {
+        let m = UnsignedLongInt::from(1000003);
+        let mc = ModuloContext::new(&m);
+        let mut seed = 11;
+        let f = random_poly(&mc, 6, &mut seed);
+        let g = random_poly(&mc, 4, &mut seed);
+        let h = random_poly(&mc, 5, &mut seed);
+
+        let composed = f.coeffs().iter().rev().fold(ModPoly::zero(&mc), |acc, c| {
+            &acc * &g + ModPoly::from_coeffs(&mc, vec![c.clone()])
+        });
+        assert_eq!(f.compose_mod(&g, &h), composed.rem(&h));
+    }
+}
@@ -0,0 +1,337 @@
+use vl_big_ints::UnsignedLongInt;
+use crate::{barret_reduction, BarretParameters};
+use crate::auxiliary::{bit_length, low_bits, significant_digits};
+use crate::context::SmallModuloContext;
+
+/// strategy for reducing products of two residues modulo a fixed modulus
+pub trait Reducer {
+    fn modulus(&self) -> &UnsignedLongInt;
+
+    /// returns `x mod self.modulus()` for `x < self.modulus()^2`
+    fn reduce(&self, x: &UnsignedLongInt) -> UnsignedLongInt;
+
+    /// word-sized arithmetic for one-digit moduli, used by [`crate::context::ModuloContext`] as a fast path
+    fn as_word(&self) -> Option<&SmallModuloContext> {
+        None
+    }
+
+    /// masking reducer for moduli `2^k`, used by [`crate::context::ModuloContext`] as a fast path
+    fn as_power_of_two(&self) -> Option<&PowerOfTwoReducer> {
+        None
+    }
+}
+
+impl Reducer for BarretParameters {
+    fn modulus(&self) -> &UnsignedLongInt {
+        self.get_val()
+    }
+
+    fn reduce(&self, x: &UnsignedLongInt) -> UnsignedLongInt {
+        barret_reduction(x, self)
+    }
+}
+
+/// reducer for moduli `2^k`, truncates the digits and masks the top one
+pub struct PowerOfTwoReducer {
+    modulo: UnsignedLongInt,
+    k: usize,
+}
+
+impl PowerOfTwoReducer {
+    /// returns `None` if `modulo` is not a power of two
+    pub fn new(modulo: &UnsignedLongInt) -> Option<Self> {
+        let ones: u32 = significant_digits(modulo).iter().map(|d| d.count_ones()).sum();
+        if ones != 1 {
+            return None;
+        }
+
+        Some(Self { modulo: modulo.clone(), k: bit_length(modulo) - 1 })
+    }
+
+    /// returns `k` for the modulus `2^k`
+    pub fn get_k(&self) -> usize {
+        self.k
+    }
+
+    /// returns `a^-1 mod 2^k` by Newton iteration `x = x * (2 - a * x)`, which doubles
+    /// the number of correct low bits every step, or `None` if `a` is even
+    pub fn inv(&self, a: &UnsignedLongInt) -> Option<UnsignedLongInt> {
+        if self.k == 0 {
+            return Some(UnsignedLongInt::from(0));
+        }
+
+        let a0 = a.digits().first().copied().unwrap_or(0);
+        if a0 & 1 == 0 {
+            return None;
+        }
+
+        // a0 is its own inverse modulo 8, every step doubles that to 64 bits
+        let mut x0 = a0;
+        for _ in 0..5 {
+            x0 = x0.wrapping_mul(2u64.wrapping_sub(a0.wrapping_mul(x0)));
+        }
+
+        let mut x = UnsignedLongInt::from(x0);
+        let mut bits = u64::BITS as usize;
+        while bits < self.k {
+            bits = (2 * bits).min(self.k);
+
+            let t = low_bits(&(&low_bits(a, bits) * &x), bits);
+            let mut two = UnsignedLongInt::from(2);
+            two.set_bit(bits);
+            x = low_bits(&(&x * &(two - t)), bits);
+        }
+
+        Some(low_bits(&x, self.k))
+    }
+}
+
+impl Reducer for PowerOfTwoReducer {
+    fn modulus(&self) -> &UnsignedLongInt {
+        &self.modulo
+    }
+
+    fn reduce(&self, x: &UnsignedLongInt) -> UnsignedLongInt {
+        low_bits(x, self.k)
+    }
+
+    fn as_power_of_two(&self) -> Option<&PowerOfTwoReducer> {
+        Some(self)
+    }
+}
+
+/// reducer for Mersenne moduli `2^n - 1`, folds the high bits onto the low ones
+pub struct MersenneReducer {
+    modulo: UnsignedLongInt,
+    n: usize,
+}
+
+impl MersenneReducer {
+    /// returns `None` if `modulo` is not of the form `2^n - 1`
+    pub fn new(modulo: &UnsignedLongInt) -> Option<Self> {
+        let n = bit_length(modulo);
+        if n == 0 || low_bits(&(modulo + UnsignedLongInt::from(1)), n) != UnsignedLongInt::from(0) {
+            return None;
+        }
+
+        Some(Self { modulo: modulo.clone(), n })
+    }
+}
+
+impl Reducer for MersenneReducer {
+    fn modulus(&self) -> &UnsignedLongInt {
+        &self.modulo
+    }
+
+    fn reduce(&self, x: &UnsignedLongInt) -> UnsignedLongInt {
+        let shift = UnsignedLongInt::from(self.n as u64);
+
+        // 2^n = 1 (mod m), so x = hi * 2^n + lo = hi + lo
+        let mut x = x.clone();
+        while bit_length(&x) > self.n {
+            x = x.shr(&shift) + low_bits(&x, self.n);
+        }
+
+        x.checked_sub(&self.modulo).unwrap_or(x)
+    }
+}
+
+/// reducer for pseudo-Mersenne moduli `2^n - c` with small `c`, folds the high bits multiplied by `c`
+///
+/// Solinas-style moduli with larger `c` work too, every fold just removes fewer bits.
+pub struct PseudoMersenneReducer {
+    modulo: UnsignedLongInt,
+    n: usize,
+    c: UnsignedLongInt,
+}
+
+impl PseudoMersenneReducer {
+    /// returns `None` if `modulo` is not of the form `2^n - c` with `c < 2^(n - 1)`
+    pub fn new(modulo: &UnsignedLongInt) -> Option<Self> {
+        let n = bit_length(modulo);
+        if n < 2 {
+            return None;
+        }
+
+        let mut power = UnsignedLongInt::from(0);
+        power.set_bit(n);
+        let c = power - modulo;
+        // c = 2^(n-1) would not shrink the folded value at all
+        if bit_length(&c) >= n - 1 {
+            return None;
+        }
+
+        Some(Self { modulo: modulo.clone(), n, c })
+    }
+
+    pub fn get_c(&self) -> &UnsignedLongInt {
+        &self.c
+    }
+}
+
+impl Reducer for PseudoMersenneReducer {
+    fn modulus(&self) -> &UnsignedLongInt {
+        &self.modulo
+    }
+
+    fn reduce(&self, x: &UnsignedLongInt) -> UnsignedLongInt {
+        let shift = UnsignedLongInt::from(self.n as u64);
+
+        // 2^n = c (mod m), so x = hi * 2^n + lo = hi * c + lo
+        let mut x = x.clone();
+        while bit_length(&x) > self.n {
+            x = x.shr(&shift) * &self.c + low_bits(&x, self.n);
+        }
+
+        while let Some(r) = x.checked_sub(&self.modulo) {
+            x = r;
+        }
+
+        x
+    }
+}
+
+/// reducer selected from the shape of the modulus, used by [`crate::context::ModuloContext::new`]
+pub enum AutoReducer {
+    Barret(BarretParameters),
+    Mersenne(MersenneReducer),
+    PseudoMersenne(PseudoMersenneReducer),
+    Word(SmallModuloContext),
+    PowerOfTwo(PowerOfTwoReducer),
+}
+
+impl AutoReducer {
+    /// picks masking for powers of two, word arithmetic for other one-digit moduli,
+    /// Mersenne or pseudo-Mersenne folding for `2^n - c` with `c` at most `n / 2` bits long,
+    /// Barrett reduction otherwise
+    pub fn new(modulo: &UnsignedLongInt) -> Self {
+        if let Some(r) = PowerOfTwoReducer::new(modulo) {
+            return AutoReducer::PowerOfTwo(r);
+        }
+
+        if let [m] = significant_digits(modulo) {
+            if *m != 0 {
+                return AutoReducer::Word(SmallModuloContext::new(*m));
+            }
+        }
+
+        if let Some(r) = MersenneReducer::new(modulo) {
+            return AutoReducer::Mersenne(r);
+        }
+
+        if let Some(r) = PseudoMersenneReducer::new(modulo) {
+            if bit_length(r.get_c()) <= r.n / 2 {
+                return AutoReducer::PseudoMersenne(r);
+            }
+        }
+
+        AutoReducer::Barret(BarretParameters::new(modulo, modulo.num_digits()))
+    }
+}
+
+impl Reducer for AutoReducer {
+    fn modulus(&self) -> &UnsignedLongInt {
+        match self {
+            AutoReducer::Barret(r) => r.modulus(),
+            AutoReducer::Mersenne(r) => r.modulus(),
+            AutoReducer::PseudoMersenne(r) => r.modulus(),
+            AutoReducer::Word(r) => r.modulus(),
+            AutoReducer::PowerOfTwo(r) => r.modulus(),
+        }
+    }
+
+    fn reduce(&self, x: &UnsignedLongInt) -> UnsignedLongInt {
+        match self {
+            AutoReducer::Barret(r) => r.reduce(x),
+            AutoReducer::Mersenne(r) => r.reduce(x),
+            AutoReducer::PseudoMersenne(r) => r.reduce(x),
+            AutoReducer::Word(r) => r.reduce(x),
+            AutoReducer::PowerOfTwo(r) => r.reduce(x),
+        }
+    }
+
+    fn as_word(&self) -> Option<&SmallModuloContext> {
+        match self {
+            AutoReducer::Word(r) => Some(r),
+            _ => None,
+        }
+    }
+
+    fn as_power_of_two(&self) -> Option<&PowerOfTwoReducer> {
+        match self {
+            AutoReducer::PowerOfTwo(r) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ModuloContext, MontgomeryContext};
+    use std::error::Error;
+    use std::str::FromStr;
+
+    const A: &str = "c0bb89c50cf9de8295a86586d798b31d365543b97dbf4242d546e086d6effbb6f53a884341b9f7999a8b160eb82d33962091dbdaab1d550f8f56ea8e42a253638ea8fb2ac8b7a564278e700b7610f96570f034ee5bfc8e6ef5de9a0b49696b841064c39f735ec412a327f91dae88bc3cb4af1971c7d82cd9082c01e4fea5abc1";
+
+    fn pow2_minus(n: usize, c: u64) -> UnsignedLongInt {
+        let mut power = UnsignedLongInt::from(0);
+        power.set_bit(n);
+        power - UnsignedLongInt::from(c)
+    }
+
+    #[test]
+    fn mersenne() -> Result<(), Box<dyn Error>> {
+        let m = pow2_minus(521, 1);
+        let a = UnsignedLongInt::from_str(A)?;
+
+        let r = MersenneReducer::new(&m).expect("modulo is Mersenne");
+        assert_eq!(r.reduce(&a), UnsignedLongInt::div(&a, &m).1);
+        assert_eq!(r.reduce(&m), UnsignedLongInt::from(0));
+        assert!(MersenneReducer::new(&pow2_minus(521, 3)).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn pseudo_mersenne() -> Result<(), Box<dyn Error>> {
+        let m = pow2_minus(255, 19);
+        let a = UnsignedLongInt::from_str(&A[..120])?;
+
+        let r = PseudoMersenneReducer::new(&m).expect("modulo is pseudo-Mersenne");
+        assert_eq!(r.get_c(), &UnsignedLongInt::from(19));
+        assert_eq!(r.reduce(&a), UnsignedLongInt::div(&a, &m).1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn auto_selection() -> Result<(), Box<dyn Error>> {
+        assert!(matches!(AutoReducer::new(&pow2_minus(61, 1)), AutoReducer::Word(_)));
+        assert!(matches!(AutoReducer::new(&pow2_minus(61, 0)), AutoReducer::PowerOfTwo(_)));
+        assert!(matches!(AutoReducer::new(&pow2_minus(300, 0)), AutoReducer::PowerOfTwo(_)));
+        assert!(matches!(AutoReducer::new(&pow2_minus(127, 1)), AutoReducer::Mersenne(_)));
+        assert!(matches!(AutoReducer::new(&pow2_minus(255, 19)), AutoReducer::PseudoMersenne(_)));
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+        assert!(matches!(AutoReducer::new(&m), AutoReducer::Barret(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn montgomery_reducer() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784ee7a0b2df68cd23a1e5e01fe37626dc77d4cd7e8d1da5365ca90ed72529f3952f")?;
+        let b = UnsignedLongInt::from_str("3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f1353ff5ff9c1a640df60811d70c202950c4c7f9c566807f17e0fca271456f001125")?;
+        let m = UnsignedLongInt::from_str("463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b")?;
+
+        let mc = ModuloContext::with_reducer(&m, MontgomeryContext::new(&m).expect("modulo is odd"));
+        let bc = ModuloContext::new(&m);
+
+        let result: UnsignedLongInt = mc.mul(&mc.modulo(&a), &mc.modulo(&b)).into();
+        assert_eq!(result, bc.mul(&bc.modulo(&a), &bc.modulo(&b)).into());
+        let result: UnsignedLongInt = mc.pow(&mc.modulo(&a), &b).into();
+        assert_eq!(result, bc.pow(&bc.modulo(&a), &b).into());
+
+        Ok(())
+    }
+}
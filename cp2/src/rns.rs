@@ -0,0 +1,385 @@
+use std::ops::*;
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::{bit_length, inv_mod_word, is_prime_word, mul_mod_word, rem_word};
+use crate::reduction::Reducer;
+
+/// largest modulus of a single channel
+pub const MAX_CHANNEL_BITS: u32 = 63;
+
+/// the `count` largest primes below `2^63`, in descending order
+pub fn word_primes(count: usize) -> Vec<u64> {
+    let mut primes = Vec::with_capacity(count);
+    let mut candidate = (1u64 << MAX_CHANNEL_BITS) - 1;
+    while primes.len() < count {
+        if is_prime_word(candidate) {
+            primes.push(candidate);
+        }
+        candidate -= 2;
+    }
+
+    primes
+}
+
+/// number represented by its residues modulo the channels of an [`RnsBasis`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RnsUint<'b> {
+    residues: Vec<u64>,
+    basis: &'b RnsBasis,
+}
+
+impl<'b> RnsUint<'b> {
+    pub fn basis(&self) -> &'b RnsBasis {
+        self.basis
+    }
+
+    pub fn residues(&self) -> &[u64] {
+        &self.residues
+    }
+}
+
+impl From<RnsUint<'_>> for UnsignedLongInt {
+    fn from(value: RnsUint<'_>) -> Self {
+        value.basis.to_uint(&value)
+    }
+}
+
+/// residue number system over pairwise coprime word-sized moduli (usually primes),
+/// every channel is independent, so channel-wise arithmetic never carries between words
+#[derive(Debug, PartialEq, Eq)]
+pub struct RnsBasis {
+    primes: Vec<u64>,
+    /// `M = p_0 * ... * p_(k - 1)`
+    modulo: UnsignedLongInt,
+    /// `partial[i] = M / p_i`
+    partial: Vec<UnsignedLongInt>,
+    /// `partial_inv[i] = (M / p_i)^-1 mod p_i`
+    partial_inv: Vec<u64>,
+    /// `mixed_radix_inv[i][j] = p_j^-1 mod p_i` for `j < i`
+    mixed_radix_inv: Vec<Vec<u64>>,
+}
+
+impl RnsBasis {
+    /// returns `None` if `primes` is empty, some channel is not in `2..2^63`
+    /// or the channels are not pairwise coprime
+    pub fn new(primes: &[u64]) -> Option<Self> {
+        if primes.is_empty() || primes.iter().any(|&p| p < 2 || p >> MAX_CHANNEL_BITS != 0) {
+            return None;
+        }
+
+        let mut mixed_radix_inv = Vec::with_capacity(primes.len());
+        for (i, &p) in primes.iter().enumerate() {
+            mixed_radix_inv.push(primes[..i].iter().map(|&p_j| inv_mod_word(p_j, p)).collect::<Option<Vec<_>>>()?);
+        }
+
+        let modulo = primes.iter().fold(UnsignedLongInt::from(1), |m, &p| m.mul_single_digit(p));
+        let partial: Vec<UnsignedLongInt> = primes.iter().map(|&p| &modulo / &UnsignedLongInt::from(p)).collect();
+        let partial_inv = partial.iter().zip(primes).map(|(m_i, &p)| inv_mod_word(rem_word(m_i, p), p)).collect::<Option<_>>()?;
+
+        Some(Self { primes: primes.to_vec(), modulo, partial, partial_inv, mixed_radix_inv })
+    }
+
+    pub fn primes(&self) -> &[u64] {
+        &self.primes
+    }
+
+    /// the dynamic range of the basis, numbers are represented modulo it
+    pub fn get_modulo(&self) -> &UnsignedLongInt {
+        &self.modulo
+    }
+
+    /// splits `x mod M` into residues
+    pub fn from_uint(&self, x: &UnsignedLongInt) -> RnsUint<'_> {
+        RnsUint {
+            residues: self.primes.iter().map(|&p| rem_word(x, p)).collect(),
+            basis: self,
+        }
+    }
+
+    /// returns `None` if the number of residues does not match or some residue is not reduced
+    pub fn from_residues(&self, residues: Vec<u64>) -> Option<RnsUint<'_>> {
+        if residues.len() != self.primes.len() || residues.iter().zip(&self.primes).any(|(r, p)| r >= p) {
+            return None;
+        }
+
+        Some(RnsUint { residues, basis: self })
+    }
+
+    /// CRT reconstruction `sum((r_i * partial_inv_i mod p_i) * partial_i) mod M`
+    pub fn to_uint(&self, x: &RnsUint) -> UnsignedLongInt {
+        let mut sum = UnsignedLongInt::from(0);
+        for (i, &r) in x.residues.iter().enumerate() {
+            let xi = mul_mod_word(r, self.partial_inv[i], self.primes[i]);
+            sum = sum + self.partial[i].mul_single_digit(xi);
+        }
+
+        // every term is below M, so at most k subtractions are needed
+        while let Some(r) = sum.checked_sub(&self.modulo) {
+            sum = r;
+        }
+
+        sum
+    }
+
+    /// mixed-radix digits `v_i < p_i` with `x = v_0 + v_1 * p_0 + v_2 * p_0 * p_1 + ...`,
+    /// computed with word arithmetic only
+    fn mixed_radix(&self, residues: &[u64]) -> Vec<u64> {
+        let mut digits: Vec<u64> = Vec::with_capacity(residues.len());
+        for (i, &p) in self.primes.iter().enumerate() {
+            let mut v = residues[i];
+            for (j, &v_j) in digits.iter().enumerate() {
+                v = mul_mod_word((v + p - v_j % p) % p, self.mixed_radix_inv[i][j], p);
+            }
+            digits.push(v);
+        }
+
+        digits
+    }
+
+    /// exact base extension: residues of the same number `x < M` in the channels of `target`
+    pub fn extend<'t>(&self, x: &RnsUint, target: &'t RnsBasis) -> RnsUint<'t> {
+        RnsUint {
+            residues: self.extend_residues(&x.residues, &target.primes),
+            basis: target,
+        }
+    }
+
+    fn extend_residues(&self, residues: &[u64], target: &[u64]) -> Vec<u64> {
+        let digits = self.mixed_radix(residues);
+        target
+            .iter()
+            .map(|&q| {
+                // Horner's rule over the mixed-radix digits, modulo q
+                digits.iter().zip(&self.primes).rev().fold(0u64, |acc, (&v, &p)| (mul_mod_word(acc, p % q, q) + v % q) % q)
+            })
+            .collect()
+    }
+
+    fn channelwise<'b, F: Fn(u64, u64, u64) -> u64>(&'b self, a: &RnsUint, b: &RnsUint, f: F) -> RnsUint<'b> {
+        debug_assert!([a.basis, b.basis].iter().all(|&basis| std::ptr::eq(basis, self) || basis == self), "numbers must belong to this basis");
+        RnsUint {
+            residues: self.primes.iter().zip(a.residues.iter().zip(&b.residues)).map(|(&p, (&x, &y))| f(x, y, p)).collect(),
+            basis: self,
+        }
+    }
+
+    pub fn add<'b>(&'b self, a: &RnsUint, b: &RnsUint) -> RnsUint<'b> {
+        self.channelwise(a, b, |x, y, p| ((x as u128 + y as u128) % p as u128) as u64)
+    }
+
+    pub fn sub<'b>(&'b self, a: &RnsUint, b: &RnsUint) -> RnsUint<'b> {
+        self.channelwise(a, b, |x, y, p| if x >= y { x - y } else { p - (y - x) })
+    }
+
+    pub fn mul<'b>(&'b self, a: &RnsUint, b: &RnsUint) -> RnsUint<'b> {
+        self.channelwise(a, b, mul_mod_word)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'b> Add<&RnsUint<'b>> for &RnsUint<'b>
+{
+    type Output = RnsUint<'b>;
+    fn add(self, rhs: &RnsUint<'b>) -> Self::Output {
+        self.basis.add(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'b> Sub<&RnsUint<'b>> for &RnsUint<'b>
+{
+    type Output = RnsUint<'b>;
+    fn sub(self, rhs: &RnsUint<'b>) -> Self::Output {
+        self.basis.sub(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl<'b> Mul<&RnsUint<'b>> for &RnsUint<'b>
+{
+    type Output = RnsUint<'b>;
+    fn mul(self, rhs: &RnsUint<'b>) -> Self::Output {
+        self.basis.mul(self, rhs)
+    }
+}
+
+/// RNS Montgomery reduction with two bases `B` and `B'`: `REDC(x) = (x + q * N) / M_B` for
+/// `q = -x * N^-1 mod M_B`, where the division is exact and happens in `B'` channel by channel
+///
+/// Can be plugged into [`crate::context::ModuloContext::with_reducer`].
+pub struct RnsMontgomery {
+    modulo: UnsignedLongInt,
+    base: RnsBasis,
+    ext: RnsBasis,
+    /// `-N^-1 mod p_i` for the channels of `B`
+    neg_n_inv: Vec<u64>,
+    /// `N mod p'_j` for the channels of `B'`
+    n_ext: Vec<u64>,
+    /// `M_B^-1 mod p'_j` for the channels of `B'`
+    m_inv_ext: Vec<u64>,
+    /// `M_B^2 mod N` in `B` and `B'`, brings `REDC(x)` back out of Montgomery form
+    r2: (Vec<u64>, Vec<u64>),
+}
+
+impl RnsMontgomery {
+    /// picks both bases from [`word_primes`], returns `None` for moduli below 2
+    pub fn new(modulo: &UnsignedLongInt) -> Option<Self> {
+        if modulo < &UnsignedLongInt::from(2) {
+            return None;
+        }
+
+        // 62 bits per prime, and two spare bits for M > 4N
+        let count = (bit_length(modulo) + 2).div_ceil(62);
+        // every word prime exceeds 2^62, so at most bit_length / 62 of them divide the modulus
+        let candidates = 2 * count + bit_length(modulo) / 62;
+        let primes: Vec<u64> = word_primes(candidates).into_iter().filter(|&p| rem_word(modulo, p) != 0).collect();
+        Self::with_bases(modulo, RnsBasis::new(&primes[..count])?, RnsBasis::new(&primes[count..2 * count])?)
+    }
+
+    /// returns `None` unless `B` and `B'` are coprime to each other, `N` is coprime to `B`
+    /// and both `M_B` and `M_B'` exceed `4N`
+    pub fn with_bases(modulo: &UnsignedLongInt, base: RnsBasis, ext: RnsBasis) -> Option<Self> {
+        let four_n = modulo.shl(2);
+        if base.get_modulo() <= &four_n || ext.get_modulo() <= &four_n {
+            return None;
+        }
+
+        let neg_n_inv = base.primes.iter().map(|&p| inv_mod_word(rem_word(modulo, p), p).map(|inv| (p - inv) % p)).collect::<Option<_>>()?;
+        let n_ext = ext.primes.iter().map(|&p| rem_word(modulo, p)).collect();
+        let m_inv_ext = ext.primes.iter().map(|&p| inv_mod_word(rem_word(base.get_modulo(), p), p)).collect::<Option<_>>()?;
+
+        let m_b = UnsignedLongInt::div(base.get_modulo(), modulo).1;
+        let r2 = UnsignedLongInt::div(&(&m_b * &m_b), modulo).1;
+        let r2 = (
+            base.primes.iter().map(|&p| rem_word(&r2, p)).collect(),
+            ext.primes.iter().map(|&p| rem_word(&r2, p)).collect(),
+        );
+
+        Some(Self { modulo: modulo.clone(), base, ext, neg_n_inv, n_ext, m_inv_ext, r2 })
+    }
+
+    pub fn base(&self) -> &RnsBasis {
+        &self.base
+    }
+
+    pub fn ext(&self) -> &RnsBasis {
+        &self.ext
+    }
+
+    /// `x * M_B^-1 mod N` up to a multiple of `N` (the result is below `2N`) for `x < N * M_B`,
+    /// given and returned in both bases
+    fn redc(&self, x: &[u64], x_ext: &[u64]) -> (Vec<u64>, Vec<u64>) {
+        let q: Vec<u64> = x.iter().zip(&self.neg_n_inv).zip(&self.base.primes).map(|((&x, &c), &p)| mul_mod_word(x, c, p)).collect();
+        let q_ext = self.base.extend_residues(&q, &self.ext.primes);
+
+        let r_ext: Vec<u64> = (0..self.ext.primes.len())
+            .map(|j| {
+                let p = self.ext.primes[j];
+                let t = (x_ext[j] as u128 + mul_mod_word(q_ext[j], self.n_ext[j], p) as u128) % p as u128;
+                mul_mod_word(t as u64, self.m_inv_ext[j], p)
+            })
+            .collect();
+        let r = self.ext.extend_residues(&r_ext, &self.base.primes);
+
+        (r, r_ext)
+    }
+}
+
+impl Reducer for RnsMontgomery {
+    fn modulus(&self) -> &UnsignedLongInt {
+        &self.modulo
+    }
+
+    /// `REDC(REDC(x) * M_B^2)`, every step works on independent word-sized channels
+    fn reduce(&self, x: &UnsignedLongInt) -> UnsignedLongInt {
+        let (x_b, x_ext) = (self.base.from_uint(x), self.ext.from_uint(x));
+        let (r, r_ext) = self.redc(&x_b.residues, &x_ext.residues);
+
+        let mul = |a: &[u64], b: &[u64], primes: &[u64]| -> Vec<u64> {
+            a.iter().zip(b).zip(primes).map(|((&a, &b), &p)| mul_mod_word(a, b, p)).collect()
+        };
+        let (t, t_ext) = (mul(&r, &self.r2.0, &self.base.primes), mul(&r_ext, &self.r2.1, &self.ext.primes));
+        let (_, r_ext) = self.redc(&t, &t_ext);
+
+        let r = self.ext.to_uint(&RnsUint { residues: r_ext, basis: &self.ext });
+        r.checked_sub(&self.modulo).unwrap_or(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::ModuloContext;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    const A: &str = "ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca67751271a7a2894784ee7a0b2df68cd23a1e5e01fe37626dc77d4cd7e8d1da5365ca90ed72529f3952f";
+    const B: &str = "3eac59a64ad86a1e503329e28f48b12ba3677c4fe0171efd46749ec57387f1353ff5ff9c1a640df60811d70c202950c4c7f9c566807f17e0fca271456f001125";
+    const M: &str = "463563730008fbffd4a9214247f6142f3c87912ab199e8a0c6e08e6c1454b96b";
+
+    #[test]
+    fn primes() {
+        let primes = word_primes(4);
+        assert_eq!(primes[0], (1 << 63) - 25);
+        assert!(primes.iter().all(|&p| is_prime_word(p) && p < 1 << 63));
+        assert!(RnsBasis::new(&[6, 9]).is_none());
+        assert!(RnsBasis::new(&[u64::MAX]).is_none());
+    }
+
+    #[test]
+    fn conversion_and_channel_arithmetic() -> Result<(), Box<dyn Error>> {
+        let basis = RnsBasis::new(&word_primes(20)).expect("primes are coprime");
+        let a = UnsignedLongInt::from_str(A)?;
+        let b = UnsignedLongInt::from_str(B)?;
+        let (ar, br) = (basis.from_uint(&a), basis.from_uint(&b));
+
+        assert_eq!(UnsignedLongInt::from(ar.clone()), a);
+        assert_eq!(UnsignedLongInt::from(&ar + &br), &a + &b);
+        assert_eq!(UnsignedLongInt::from(&ar - &br), &a - &b);
+        // the product of two 512-bit numbers still fits into 20 * 63 bits
+        assert_eq!(UnsignedLongInt::from(&ar * &br), &a * &b);
+
+        assert!(basis.from_residues(vec![0; 3]).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn base_extension() -> Result<(), Box<dyn Error>> {
+        let primes = word_primes(12);
+        let (from, to) = (RnsBasis::new(&primes[..9]).expect("coprime"), RnsBasis::new(&primes[9..]).expect("coprime"));
+        let a = UnsignedLongInt::from_str(A)?;
+
+        let extended = from.extend(&from.from_uint(&a), &to);
+        assert_eq!(extended, to.from_uint(&a));
+
+        Ok(())
+    }
+
+    #[test]
+    fn montgomery_backend() -> Result<(), Box<dyn Error>> {
+        let a = UnsignedLongInt::from_str(A)?;
+        let b = UnsignedLongInt::from_str(B)?;
+        let m = UnsignedLongInt::from_str(M)?;
+
+        let rc = ModuloContext::with_reducer(&m, RnsMontgomery::new(&m).expect("modulo is large enough"));
+        let bc = ModuloContext::new(&m);
+
+        assert_eq!(rc.modulo(&a).value(), bc.modulo(&a).value());
+        assert_eq!(rc.mul(&rc.modulo(&a), &rc.modulo(&b)).value(), bc.mul(&bc.modulo(&a), &bc.modulo(&b)).value());
+        assert_eq!(rc.pow(&rc.modulo(&a), &m).value(), bc.pow(&bc.modulo(&a), &m).value());
+
+        // even moduli work too, only gcd(N, M_B) = 1 is required
+        let even = UnsignedLongInt::from_str("0b8c9870a515714526f4a3731f6b6dda")?;
+        let rc = ModuloContext::with_reducer(&even, RnsMontgomery::new(&even).expect("modulo is large enough"));
+        let bc = ModuloContext::new(&even);
+        assert_eq!(rc.pow(&rc.modulo(&a), &b).value(), bc.pow(&bc.modulo(&a), &b).value());
+
+        // divisible by the first three candidate primes, the bases are drawn from the following ones
+        let product = word_primes(3).iter().fold(UnsignedLongInt::from(1), |acc, &p| acc * UnsignedLongInt::from(p));
+        let rc = ModuloContext::with_reducer(&product, RnsMontgomery::new(&product).expect("modulo is large enough"));
+        let bc = ModuloContext::new(&product);
+        assert_eq!(rc.pow(&rc.modulo(&a), &b).value(), bc.pow(&bc.modulo(&a), &b).value());
+
+        Ok(())
+    }
+}
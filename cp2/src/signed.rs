@@ -0,0 +1,144 @@
+use std::fmt::{Display, Formatter};
+use std::ops::*;
+use vl_big_ints::UnsignedLongInt;
+use crate::auxiliary::EvenOdd;
+
+/// sign-magnitude long integer, used where intermediate values may go below zero
+/// (Bézout coefficients, signed residues)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedLongInt {
+    negative: bool,
+    magnitude: UnsignedLongInt,
+}
+
+impl SignedLongInt {
+    pub fn new(negative: bool, magnitude: UnsignedLongInt) -> Self {
+        // zero is always stored as non-negative so that derived equality holds
+        let negative = negative && magnitude != UnsignedLongInt::from(0);
+        Self { negative, magnitude }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude == UnsignedLongInt::from(0)
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.magnitude.is_even()
+    }
+
+    pub fn magnitude(&self) -> &UnsignedLongInt {
+        &self.magnitude
+    }
+
+    /// divides an even number by two
+    pub fn half(&self) -> Self {
+        debug_assert!(self.is_even());
+        Self::new(self.negative, self.magnitude.shr(&UnsignedLongInt::from(1)))
+    }
+
+    /// returns the representative of `self` in `[0, m)`
+    pub fn rem_euclid(&self, m: &UnsignedLongInt) -> UnsignedLongInt {
+        let r = UnsignedLongInt::div(&self.magnitude, m).1;
+        if self.negative && r != UnsignedLongInt::from(0) {
+            m - r
+        } else {
+            r
+        }
+    }
+
+    pub fn add(&self, rhs: &Self) -> Self {
+        if self.negative == rhs.negative {
+            return Self::new(self.negative, &self.magnitude + &rhs.magnitude);
+        }
+
+        // different signs: the result takes the sign of the larger magnitude
+        if self.magnitude >= rhs.magnitude {
+            Self::new(self.negative, &self.magnitude - &rhs.magnitude)
+        } else {
+            Self::new(rhs.negative, &rhs.magnitude - &self.magnitude)
+        }
+    }
+
+    pub fn sub(&self, rhs: &Self) -> Self {
+        self.add(&-rhs)
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Self {
+        if self.is_zero() || rhs.is_zero() {
+            return Self::from(0);
+        }
+
+        Self::new(self.negative != rhs.negative, &self.magnitude * &rhs.magnitude)
+    }
+}
+
+impl From<UnsignedLongInt> for SignedLongInt {
+    fn from(value: UnsignedLongInt) -> Self {
+        Self::new(false, value)
+    }
+}
+
+impl From<&UnsignedLongInt> for SignedLongInt {
+    fn from(value: &UnsignedLongInt) -> Self {
+        Self::new(false, value.clone())
+    }
+}
+
+impl From<i64> for SignedLongInt {
+    fn from(value: i64) -> Self {
+        Self::new(value < 0, UnsignedLongInt::from(value.unsigned_abs()))
+    }
+}
+
+impl Display for SignedLongInt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", &self.magnitude)
+    }
+}
+
+impl Neg for &SignedLongInt {
+    type Output = SignedLongInt;
+
+    fn neg(self) -> Self::Output {
+        SignedLongInt::new(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Neg for SignedLongInt {
+    type Output = SignedLongInt;
+
+    fn neg(self) -> Self::Output {
+        SignedLongInt::new(!self.negative, self.magnitude)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl Add<&SignedLongInt> for &SignedLongInt {
+    type Output = SignedLongInt;
+    fn add(self, rhs: &SignedLongInt) -> Self::Output {
+        SignedLongInt::add(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl Sub<&SignedLongInt> for &SignedLongInt {
+    type Output = SignedLongInt;
+    fn sub(self, rhs: &SignedLongInt) -> Self::Output {
+        SignedLongInt::sub(self, rhs)
+    }
+}
+
+#[auto_impl_ops::auto_ops]
+impl Mul<&SignedLongInt> for &SignedLongInt {
+    type Output = SignedLongInt;
+    fn mul(self, rhs: &SignedLongInt) -> Self::Output {
+        SignedLongInt::mul(self, rhs)
+    }
+}
@@ -1,7 +1,7 @@
 use super::*;
 use std::error::Error;
 use std::str::FromStr;
-use crate::context::{ModuloContext, ModuloUint};
+use crate::context::ModuloContext;
 
 #[test]
 fn gcd1_test() -> Result<(), Box<dyn Error>> {
@@ -39,6 +39,47 @@ fn lcm_test() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn extended_gcd_test() -> Result<(), Box<dyn Error>> {
+    let a = UnsignedLongInt::from_str("62d972e5a1c8f8a1ddb169e740bb706698eefa9efa30b65df1de1556047afc4")?;
+    let b = UnsignedLongInt::from_str("88f1979cc28247ede75fda269e4b892a")? / UnsignedLongInt::from(2);
+
+    let (g, x, y) = extended_gcd(&a, &b);
+    let expected = UnsignedLongInt::from_str("4478cbce614123f6f3afed134f25c495")?;
+
+    assert_eq!(g, expected);
+    assert_eq!(&x * &SignedLongInt::from(&a) + &y * &SignedLongInt::from(&b), SignedLongInt::from(g));
+    Ok(())
+}
+
+#[test]
+fn binary_extended_gcd_test() -> Result<(), Box<dyn Error>> {
+    let a = UnsignedLongInt::from_str("62d972e5a1c8f8a1ddb169e740bb706698eefa9efa30b65df1de1556047afc4")?;
+    let b = UnsignedLongInt::from_str("88f1979cc28247ede75fda269e4b892a")?;
+
+    let (g, x, y) = binary_extended_gcd(&a, &b);
+
+    assert_eq!(g, gcd(&a, &b));
+    assert_eq!(&x * &SignedLongInt::from(&a) + &y * &SignedLongInt::from(&b), SignedLongInt::from(g));
+    Ok(())
+}
+
+#[test]
+fn mod_inverse_test() -> Result<(), Box<dyn Error>> {
+    let a = UnsignedLongInt::from_str("332620a94e016efe4880db1392fef137ac4ed9c5449395a7d55bc40328e20e77")?;
+    let odd = UnsignedLongInt::from_str("71df139f99653b654069b68d5c693c2c35d7740f1b2d2331766be1e5cd483a3b")?;
+    let even = UnsignedLongInt::from_str("71df139f99653b654069b68d5c693c2c35d7740f1b2d2331766be1e5cd483a3a")?;
+
+    for m in [&odd, &even] {
+        let inverse = mod_inverse(&a, m).expect("a is coprime with m");
+        assert_eq!(mul_mod(&a, &inverse, m), UnsignedLongInt::from(1));
+    }
+
+    assert_eq!(mod_inverse(&UnsignedLongInt::from(6), &UnsignedLongInt::from(9)), None);
+    assert_eq!(mod_inverse(&UnsignedLongInt::from(6), &UnsignedLongInt::from(1)), Some(UnsignedLongInt::from(0)));
+    Ok(())
+}
+
 #[test]
 fn barret_reduction_test() -> Result<(), Box<dyn Error>> {
     let a = UnsignedLongInt::from_str("c0bb89c50cf9de8295a86586d798b31d365543b97dbf4242d546e086d6effbb6f53a884341b9f7999a8b160eb82d33962091dbdaab1d550f8f56ea8e42a253638ea8fb2ac8b7a564278e700b7610f96570f034ee5bfc8e6ef5de9a0b49696b841064c39f735ec412a327f91dae88bc3cb4af1971c7d82cd9082c01e4fea5abc1")?;
@@ -62,6 +103,32 @@ fn power_mod_barret_test() -> Result<(), Box<dyn Error>> {
 
     assert_eq!(power_mod_barret(&a, &b, &m), expected);
 
+    let mc = ModuloContext::new(&m);
+    let amod = mc.modulo(&a);
+    assert_eq!(mc.pow(&amod, &b).value(), &expected);
+    assert_eq!(mc.pow_k_ary(&amod, &b, 3).value(), &expected);
+    assert_eq!(mc.pow_left_to_right(&amod, &b).value(), &expected);
+
+    Ok(())
+}
+
+#[test]
+fn free_functions_cached() -> Result<(), Box<dyn Error>> {
+    // a short base with a long modulus used to size the Barrett parameters wrongly
+    let a = UnsignedLongInt::from(3);
+    let b = UnsignedLongInt::from_str("ea5b5b3ebdb1bfc379e3463138434bfcc1fffd7cb70ca677")?;
+    let m = UnsignedLongInt::from_str("8d1fc443466f05e22ec69316a2c609d2626cfbceabf45b8fe79f1e4ae9808aff")?;
+
+    let mc = ModuloContext::new(&m);
+    let amod = mc.modulo(&a);
+    assert_eq!(&power_mod_barret(&a, &b, &m), mc.pow(&amod, &b).value());
+    assert_eq!(&square_mod(&b, &m), mc.modulo(&b).pow_u64(2).value());
+    assert_eq!(&sub_mod(&a, &b, &m), mc.sub(&amod, &mc.modulo(&b)).value());
+
+    let hits = cache::global().stats().hits;
+    add_mod(&a, &b, &m);
+    assert!(cache::global().stats().hits > hits);
+
     Ok(())
 }
 
@@ -99,13 +166,13 @@ fn equality_2() -> Result<(), Box<dyn Error>> {
     let nmodm = mc.modulo(&n);
 
     let mult = mc.mul(&amodm, &nmodm);
-    let mut summ = mc.modulo(&UnsignedLongInt::from(0));
+    let mut summ = mc.zero();
 
     for i in 0..N {
         summ = mc.add(&summ, &amodm);
     }
 
-    assert_eq!(<ModuloUint<'_> as Into<UnsignedLongInt>>::into(mult), <ModuloUint<'_> as Into<UnsignedLongInt>>::into(summ));
+    assert_eq!(mult, summ);
     Ok(())
 }
 
@@ -124,7 +191,7 @@ fn equality_3_1() -> Result<(), Box<dyn Error>> {
 
     let phi = &mers - UnsignedLongInt::from(1);
 
-    assert_eq!(<ModuloUint<'_> as Into<UnsignedLongInt>>::into(mc.pow(&amodm, &phi)), UnsignedLongInt::from(1));
+    assert!(mc.pow(&amodm, &phi).is_one());
 
     Ok(())
 }
@@ -142,7 +209,7 @@ fn equality_3_2() -> Result<(), Box<dyn Error>> {
     let amodm = mc.modulo(&a);
 
 
-    assert_eq!(<ModuloUint<'_> as Into<UnsignedLongInt>>::into(mc.pow(&amodm, &phi_n)), UnsignedLongInt::from(1));
+    assert!(mc.pow(&amodm, &phi_n).is_one());
 
     Ok(())
 }